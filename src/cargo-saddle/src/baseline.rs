@@ -0,0 +1,218 @@
+//! Baseline files recording known conflicts so that only new ones fail the check.
+//!
+//! Conflicts are keyed by the name of the offending scope, the component and the way in which the
+//! scope borrows it rather than by their rendered text or source locations so that unrelated edits
+//! don't invalidate the baseline. Scopes sharing their name with another scope of the binary, such
+//! as those of `scope!` blocks, are keyed by their location as well. Calling contexts aren't recorded either: every conflict is
+//! reported once whatever the context depth, so a baseline applies to checks at any depth.
+
+use std::{fs, path::Path};
+
+use anyhow::Context;
+use rustc_hash::FxHashSet;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    diagnostic::{Diagnostic, ScopeKeys},
+    validator::Mutability,
+};
+
+const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub scope: String,
+    pub component: String,
+    pub mutability: Mutability,
+}
+
+impl BaselineEntry {
+    /// Keys the diagnostic's scope using the keys of the binary it was found in.
+    pub fn of(diagnostic: &Diagnostic, keys: &ScopeKeys) -> Self {
+        Self {
+            scope: keys.key(&diagnostic.scope),
+            component: diagnostic.component.clone(),
+            mutability: diagnostic.mutability,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Baseline {
+    pub version: u32,
+    /// The known conflicts, sorted so that the file diffs cleanly.
+    pub conflicts: Vec<BaselineEntry>,
+}
+
+impl Baseline {
+    pub fn of(entries: impl IntoIterator<Item = BaselineEntry>) -> Self {
+        let mut conflicts = entries.into_iter().collect::<Vec<_>>();
+
+        conflicts.sort();
+        conflicts.dedup();
+
+        Self {
+            version: VERSION,
+            conflicts,
+        }
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let file = fs::read_to_string(path).context("failed to read baseline file")?;
+        let baseline = serde_json::from_str::<Self>(&file).context("malformed baseline file")?;
+
+        anyhow::ensure!(
+            baseline.version == VERSION,
+            "The baseline file {} has unsupported version {}.",
+            path.display(),
+            baseline.version,
+        );
+
+        Ok(baseline)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let mut file = serde_json::to_string_pretty(self)?;
+        file.push('\n');
+        fs::write(path, file).context("failed to write baseline file")
+    }
+
+    pub fn contains(&self, entry: &BaselineEntry) -> bool {
+        self.conflicts.contains(entry)
+    }
+
+    /// Lists the entries which don't correspond to any of the provided ones.
+    pub fn stale_entries<'e>(
+        &self,
+        entries: impl IntoIterator<Item = &'e BaselineEntry>,
+    ) -> Vec<&BaselineEntry> {
+        let current = entries.into_iter().collect::<FxHashSet<_>>();
+
+        self.conflicts
+            .iter()
+            .filter(|entry| !current.contains(*entry))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostic::{ConflictKind, Responsibility, ScopeRef, Severity};
+
+    fn conflict(scope: &str, component: &str, mutability: Mutability) -> Diagnostic {
+        conflict_at(scope, &format!("src/{scope}.rs:1:1"), component, mutability)
+    }
+
+    fn conflict_at(
+        scope: &str,
+        defined_at: &str,
+        component: &str,
+        mutability: Mutability,
+    ) -> Diagnostic {
+        Diagnostic {
+            kind: ConflictKind::BorrowConflict,
+            severity: Severity::Error,
+            scope: ScopeRef {
+                id: format!("app::{scope}@{defined_at}"),
+                name: scope.to_string(),
+                defined_at: defined_at.to_string(),
+            },
+            component: component.to_string(),
+            mutability,
+            held_mutability: Mutability::Mutable,
+            explanation: Responsibility::default(),
+            cycle: None,
+            contexts: Vec::new(),
+            concurrency: None,
+            reached_from: Vec::new(),
+        }
+    }
+
+    fn entries(diagnostics: &[Diagnostic]) -> Vec<BaselineEntry> {
+        let keys = ScopeKeys::new(diagnostics.iter().map(|diagnostic| &diagnostic.scope));
+        diagnostics
+            .iter()
+            .map(|diagnostic| BaselineEntry::of(diagnostic, &keys))
+            .collect()
+    }
+
+    #[test]
+    fn entries_are_sorted_and_deduplicated() {
+        let baseline = Baseline::of(entries(&[
+            conflict("render", "u32", Mutability::Immutable),
+            conflict("physics", "u64", Mutability::Mutable),
+            conflict("render", "u32", Mutability::Immutable),
+        ]));
+
+        let entries = baseline
+            .conflicts
+            .iter()
+            .map(|entry| (entry.scope.as_str(), entry.component.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(entries, [("physics", "u64"), ("render", "u32")]);
+    }
+
+    #[test]
+    fn known_and_stale_conflicts_are_found() {
+        let baseline = Baseline::of(entries(&[
+            conflict("render", "u32", Mutability::Immutable),
+            conflict("physics", "u64", Mutability::Mutable),
+        ]));
+
+        let current = entries(&[
+            conflict("render", "u32", Mutability::Immutable),
+            conflict("render", "u32", Mutability::Mutable),
+        ]);
+        assert!(baseline.contains(&current[0]));
+        assert!(!baseline.contains(&current[1]));
+
+        let stale = baseline.stale_entries(&current);
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].scope, "physics");
+    }
+
+    #[test]
+    fn same_named_scopes_are_told_apart_by_location() {
+        let block = "InlineBlock<app::Render>";
+        let current = entries(&[
+            conflict_at(block, "src/render.rs:10:5", "u32", Mutability::Mutable),
+            conflict_at(block, "src/render.rs:20:5", "u32", Mutability::Mutable),
+        ]);
+        let baseline = Baseline::of([current[0].clone()]);
+
+        assert_eq!(
+            current[0].scope,
+            "InlineBlock<app::Render> (defined at src/render.rs:10:5)"
+        );
+        assert!(baseline.contains(&current[0]));
+        assert!(!baseline.contains(&current[1]));
+        assert!(baseline.stale_entries(&current).is_empty());
+    }
+
+    #[test]
+    fn baselines_round_trip() {
+        let path =
+            std::env::temp_dir().join(format!("saddle-baseline-{}.json", std::process::id()));
+        let baseline = Baseline::of(entries(&[conflict("render", "u32", Mutability::Immutable)]));
+
+        baseline.save(&path).unwrap();
+        let loaded = Baseline::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.unwrap().conflicts, baseline.conflicts);
+    }
+
+    #[test]
+    fn unsupported_versions_are_rejected() {
+        let path = std::env::temp_dir().join(format!(
+            "saddle-baseline-version-{}.json",
+            std::process::id()
+        ));
+        fs::write(&path, r#"{ "version": 0, "conflicts": [] }"#).unwrap();
+        let loaded = Baseline::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(loaded.is_err());
+    }
+}
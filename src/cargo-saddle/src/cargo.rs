@@ -0,0 +1,174 @@
+//! Integration with `cargo build` for finding the binaries to analyze.
+
+use std::{
+    env,
+    io::{BufRead, BufReader},
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+use anyhow::Context;
+use serde_json::Value;
+
+/// An executable produced by the build.
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    /// The name of the target which produced the executable.
+    pub name: String,
+    /// The path to the executable.
+    pub path: String,
+    /// Whether the executable is a test or benchmark harness.
+    pub harness: bool,
+}
+
+fn cargo_command() -> Command {
+    // Cargo tells subcommands which binary invoked them.
+    Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+}
+
+/// Finds the root directory of the current workspace, if we're in one.
+pub fn workspace_root() -> Option<PathBuf> {
+    let output = cargo_command()
+        .args(["locate-project", "--workspace", "--message-format", "plain"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let manifest = PathBuf::from(String::from_utf8(output.stdout).ok()?.trim_end());
+    manifest.parent().map(PathBuf::from)
+}
+
+/// Parses a line of `cargo build --message-format=json` output, returning the executable it
+/// reports if any.
+fn parse_artifact(line: &str) -> Option<Artifact> {
+    let message = serde_json::from_str::<Value>(line).ok()?;
+
+    if message["reason"] != "compiler-artifact" {
+        return None;
+    }
+
+    let path = message["executable"].as_str()?;
+
+    // Build scripts are executables too but never contain the user's scopes.
+    let kinds = message["target"]["kind"].as_array();
+    if kinds.is_some_and(|kinds| kinds.iter().any(|kind| kind == "custom-build")) {
+        return None;
+    }
+
+    Some(Artifact {
+        name: message["target"]["name"]
+            .as_str()
+            .unwrap_or(path)
+            .to_string(),
+        path: path.to_string(),
+        harness: message["profile"]["test"] == true,
+    })
+}
+
+/// Runs `cargo build` with the provided arguments and collects every executable it produces.
+/// Compiler diagnostics are rendered to stderr as usual.
+pub fn build(args: &[String]) -> anyhow::Result<Vec<Artifact>> {
+    let mut child = cargo_command()
+        .arg("build")
+        .arg("--message-format=json-render-diagnostics")
+        .args(args)
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("failed to run cargo build")?;
+
+    let mut artifacts = Vec::new();
+
+    for line in BufReader::new(child.stdout.take().unwrap()).lines() {
+        let line = line.context("failed to read output of cargo build")?;
+        artifacts.extend(parse_artifact(&line));
+    }
+
+    let status = child.wait().context("failed to run cargo build")?;
+    anyhow::ensure!(status.success(), "Failed to build the binaries to analyze.");
+    anyhow::ensure!(
+        !artifacts.is_empty(),
+        "The build did not produce any binaries to analyze."
+    );
+
+    Ok(artifacts)
+}
+
+/// Lists the names of the tests and benchmarks in a harness executable without running them.
+pub fn list_tests(path: &str) -> anyhow::Result<Vec<String>> {
+    let output = Command::new(path)
+        .args(["--list", "--format", "terse"])
+        .stderr(Stdio::inherit())
+        .output()
+        .with_context(|| format!("failed to list the tests of {path}"))?;
+
+    anyhow::ensure!(
+        output.status.success(),
+        "Failed to list the tests of {path}."
+    );
+
+    Ok(parse_test_list(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parses the output of a harness run with `--list --format terse`. Each line has the form
+/// `path::to::test: test` or `path::to::bench: bench`.
+fn parse_test_list(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            line.strip_suffix(": test")
+                .or_else(|| line.strip_suffix(": bench"))
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn executables_are_collected() {
+        let artifact = parse_artifact(
+            r#"{"reason":"compiler-artifact","target":{"kind":["bin"],"name":"game"},"profile":{"test":false},"executable":"/target/debug/game"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(artifact.name, "game");
+        assert_eq!(artifact.path, "/target/debug/game");
+        assert!(!artifact.harness);
+
+        let harness = parse_artifact(
+            r#"{"reason":"compiler-artifact","target":{"kind":["lib"],"name":"game"},"profile":{"test":true},"executable":"/target/debug/deps/game-1234"}"#,
+        )
+        .unwrap();
+        assert!(harness.harness);
+    }
+
+    #[test]
+    fn other_messages_are_skipped() {
+        for line in [
+            r#"{"reason":"compiler-artifact","target":{"kind":["lib"],"name":"game"},"profile":{"test":false},"executable":null}"#,
+            r#"{"reason":"compiler-artifact","target":{"kind":["custom-build"],"name":"build-script-build"},"profile":{"test":false},"executable":"/target/debug/build/game-1234/build-script-build"}"#,
+            r#"{"reason":"build-finished","success":true}"#,
+            "Compiling game v0.1.0",
+        ] {
+            assert!(parse_artifact(line).is_none(), "{line}");
+        }
+    }
+
+    #[test]
+    fn tests_and_benches_are_listed() {
+        let tests = parse_test_list(
+            "physics::tests::gravity: test\n\
+             render::frame: bench\n\
+             \n\
+             2 tests, 0 benchmarks\n",
+        );
+
+        assert_eq!(tests, ["physics::tests::gravity", "render::frame"]);
+    }
+}
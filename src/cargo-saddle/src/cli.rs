@@ -1,17 +1,32 @@
-use std::fs;
+use std::{env, fs, path::PathBuf};
 
 use anyhow::Context;
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use rustc_hash::FxHashSet;
+use serde::Deserialize;
 
 use crate::{
-    decoder::{decode_binary, DecoderEntryKind},
-    validator::{BorrowMeta, CallMeta, ComponentId, ComponentMeta, ScopeId, ScopeMeta, Validator},
+    baseline::{Baseline, BaselineEntry},
+    cargo,
+    config::{Config, CONFIG_FILE},
+    decoder::{decode_binary, DecodedBinary, DecoderEntryKind},
+    diagnostic::{Diagnostic, ScopeRef, Severity, Summary, UnreachedScope, UNKNOWN_LOCATION},
+    diff::GraphDiff,
+    elf::{self, Attribution},
+    graph,
+    sarif::to_sarif,
+    validator::{
+        BorrowMeta, CallMeta, ComponentId, ComponentMeta, GraphFilter, ScopeId, ScopeMeta,
+        Validator,
+    },
+    why,
 };
 
 #[derive(Debug, Parser)]
 #[command(
     author = "radbuglet",
     version = "0.1.0",
+    bin_name = "cargo saddle",
     about = "Statically validates saddle borrow rules on the chosen binary",
     long_about = None,
 )]
@@ -24,95 +39,845 @@ struct Args {
 enum Commands {
     #[command()]
     Check {
-        #[arg(help = "The path to the binary being analyzed", long = None)]
-        path: String,
+        #[command(flatten)]
+        binary: BinaryArgs,
+
+        #[arg(
+            help = "The format in which diagnostics are reported [default: human]",
+            long,
+            value_enum
+        )]
+        format: Option<OutputFormat>,
+
+        #[arg(
+            help = "Only report conflicts missing from this baseline file, and flag its entries which no longer occur",
+            long
+        )]
+        baseline: Option<String>,
+
+        #[arg(
+            help = "Check each scope separately for every sequence of up to this many calls leading to it, reporting the calls which cause each conflict",
+            long,
+            default_value_t = 0
+        )]
+        context_depth: usize,
+    },
+    #[command(about = "Writes the current conflicts to a baseline file")]
+    Baseline {
+        #[command(flatten)]
+        binary: BinaryArgs,
+
+        #[arg(
+            help = "The baseline file to write [default: saddle-baseline.json]",
+            long,
+            short
+        )]
+        output: Option<String>,
+    },
+    #[command(about = "Exports the scope call graph of the chosen binary")]
+    Graph {
+        #[command(flatten)]
+        binary: BinaryArgs,
+
+        #[arg(
+            help = "The format in which the graph is written",
+            long,
+            value_enum,
+            default_value_t = GraphFormat::Dot
+        )]
+        format: GraphFormat,
+
+        #[arg(help = "Only include scopes reachable from this scope", long)]
+        from: Option<String>,
+
+        #[arg(help = "Only include scopes which can reach this scope", long)]
+        to: Option<String>,
+
+        #[arg(
+            help = "Only include scopes which borrow or grant this component",
+            long
+        )]
+        component: Option<String>,
+
+        #[arg(help = "The file to write the graph to instead of stdout", long, short)]
+        output: Option<String>,
     },
+    #[command(about = "Explains how a component may be held when a scope is entered")]
+    Why {
+        #[arg(help = "The scope being entered", long = None)]
+        scope: String,
+
+        #[arg(help = "The component which may be held", long = None)]
+        component: String,
+
+        // Comes after the required positionals since its path is optional.
+        #[command(flatten)]
+        binary: BinaryArgs,
+
+        #[arg(
+            help = "The number of shortest call chains to print",
+            long,
+            short = 'k',
+            default_value_t = 1
+        )]
+        top: usize,
+    },
+    #[command(about = "Compares the scope graphs of two builds of a binary")]
+    Diff {
+        #[arg(help = "The path to the old build of the binary", long = None)]
+        old: String,
+
+        #[arg(help = "The path to the new build of the binary", long = None)]
+        new: String,
+
+        #[arg(
+            help = "The format in which the differences are reported",
+            long,
+            value_enum,
+            default_value_t = DiffFormat::Human
+        )]
+        format: DiffFormat,
+
+        #[command(flatten)]
+        attribution: AttributionArgs,
+    },
+}
+
+#[derive(Debug, clap::Args)]
+struct BinaryArgs {
+    #[arg(
+        help = "The path to the binary being analyzed. If omitted, the binaries of the current package are built and analyzed",
+        long = None
+    )]
+    path: Option<String>,
+
+    #[command(flatten)]
+    build: BuildArgs,
+
+    #[command(flatten)]
+    attribution: AttributionArgs,
+}
+
+impl BinaryArgs {
+    fn attribution(&self) -> Option<Attribution> {
+        self.attribution.attribution()
+    }
+}
+
+#[derive(Debug, clap::Args)]
+struct AttributionArgs {
+    #[arg(
+        help = "Attribute each declaration to the functions referencing it using the ELF symbol table",
+        long
+    )]
+    elf: bool,
+
+    #[arg(
+        help = "Attribute each declaration to its source location using the binary's debug info",
+        long,
+        conflicts_with = "elf"
+    )]
+    debug_info: bool,
+}
+
+impl AttributionArgs {
+    fn attribution(&self) -> Option<Attribution> {
+        if self.elf {
+            Some(Attribution::Function)
+        } else if self.debug_info {
+            Some(Attribution::DebugInfo)
+        } else {
+            None
+        }
+    }
+}
+
+// Flags forwarded to `cargo build` when no path is given.
+#[derive(Debug, clap::Args)]
+struct BuildArgs {
+    #[arg(help = "Package to build", long, short = 'p', conflicts_with = "path")]
+    package: Vec<String>,
+
+    #[arg(
+        help = "Build only the specified binary",
+        long,
+        conflicts_with = "path"
+    )]
+    bin: Vec<String>,
+
+    #[arg(
+        help = "Build only the specified example",
+        long,
+        conflicts_with = "path"
+    )]
+    example: Vec<String>,
+
+    #[arg(
+        help = "Build in release mode, with optimizations",
+        long,
+        conflicts_with = "path"
+    )]
+    release: bool,
+
+    #[arg(
+        help = "Space or comma separated list of features to activate",
+        long,
+        short = 'F',
+        conflicts_with = "path"
+    )]
+    features: Vec<String>,
+
+    #[arg(
+        help = "Check test harnesses instead, reporting the tests which reach each conflict",
+        long,
+        conflicts_with = "path"
+    )]
+    tests: bool,
+
+    #[arg(
+        help = "Check benchmark harnesses instead, reporting the benchmarks which reach each conflict",
+        long,
+        conflicts_with = "path"
+    )]
+    benches: bool,
+}
+
+impl BuildArgs {
+    fn to_cargo_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        for (flag, values) in [
+            ("--package", &self.package),
+            ("--bin", &self.bin),
+            ("--example", &self.example),
+            ("--features", &self.features),
+        ] {
+            for value in values {
+                args.push(flag.to_string());
+                args.push(value.clone());
+            }
+        }
+
+        for (flag, enabled) in [
+            ("--release", self.release),
+            ("--tests", self.tests),
+            ("--benches", self.benches),
+        ] {
+            if enabled {
+                args.push(flag.to_string());
+            }
+        }
+
+        args
+    }
+}
+
+/// A binary selected for analysis.
+struct Target {
+    name: String,
+    binary: DecodedBinary,
+    /// The tests or benchmarks entering each scope, keyed by the scope's ID.
+    entry_points: Vec<(String, String)>,
+}
+
+/// The outcome of checking a single target.
+struct Report<'t> {
+    target: &'t Target,
+    /// The conflicts found, excluding those suppressed by the baseline.
+    diagnostics: Vec<Diagnostic>,
+    /// The number of conflicts suppressed by the baseline.
+    suppressed: usize,
+    summary: Summary,
+    unreached: Vec<UnreachedScope>,
+    undefined: Vec<ScopeRef>,
+}
+
+#[derive(Debug, Copy, Clone, ValueEnum)]
+enum GraphFormat {
+    Dot,
+    Mermaid,
+    Graphml,
+}
+
+#[derive(Debug, Copy, Clone, ValueEnum)]
+enum DiffFormat {
+    Human,
+    Json,
+}
+
+#[derive(Debug, Copy, Clone, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Human,
+    Json,
+    Sarif,
 }
 
 pub fn main_inner() -> anyhow::Result<()> {
     color_backtrace::install();
-    let args = Args::parse();
+
+    // When run as `cargo saddle`, cargo passes the name of the subcommand as the first argument.
+    let mut args = env::args_os().collect::<Vec<_>>();
+    if args.get(1).is_some_and(|arg| arg == "saddle") {
+        args.remove(1);
+    }
+
+    let args = Args::parse_from(args);
+    let config = Config::discover()?;
 
     match &args.command {
-        Commands::Check { path } => {
-            // Load the file
-            let file = fs::read(path).context("failed to read file to be analyzed")?;
-
-            // Load all declarations
-            let mut validator = Validator::default();
-            let mut found_any_directive = false;
-
-            decode_binary(&file, |mode, arg_1, arg_2| {
-                found_any_directive = true;
-
-                validator.annotate_scope(
-                    ScopeId(arg_1.clone(), []),
-                    ScopeMeta {
-                        _dummy: [],
-                        name: arg_1.clone(),
-                        defined_at: "<unknown>",
-                    },
+        Commands::Check {
+            binary,
+            format,
+            baseline,
+            context_depth,
+        } => {
+            let targets = load_targets(binary, &config)?;
+            let format = format.or(config.format).unwrap_or(OutputFormat::Human);
+            let baseline = baseline
+                .as_deref()
+                .map(PathBuf::from)
+                .or_else(|| config.baseline.clone())
+                .map(|path| Baseline::load(&path))
+                .transpose()?;
+            let mut unmatched_roots = config.roots.iter().map(String::as_str).collect::<Vec<_>>();
+            let mut all_entries = Vec::new();
+            let mut reports = Vec::new();
+
+            for target in &targets {
+                let mut validator = build_validator(target, &config)?;
+                validator.set_context_depth(*context_depth);
+
+                let unmatched = validator.unmatched_roots();
+                unmatched_roots.retain(|root| unmatched.contains(root));
+
+                // Validate graph
+                let mut diagnostics = validator.validate();
+                let keys = validator.scope_keys();
+                all_entries.extend(
+                    diagnostics
+                        .iter()
+                        .map(|diagnostic| BaselineEntry::of(diagnostic, &keys)),
                 );
 
-                match mode {
-                    DecoderEntryKind::Dep(mutability) => {
-                        validator.annotate_component(
-                            ComponentId(arg_2.clone(), []),
-                            ComponentMeta {
-                                _dummy: [],
-                                name: arg_2.clone(),
-                            },
-                        );
+                let found = diagnostics.len();
+                if let Some(baseline) = &baseline {
+                    diagnostics.retain(|diagnostic| {
+                        !baseline.contains(&BaselineEntry::of(diagnostic, &keys))
+                    });
+                }
 
-                        validator.push_access(
-                            ScopeId(arg_1, []),
-                            ComponentId(arg_2, []),
-                            mutability,
-                            BorrowMeta {
-                                def_path: "<unknown>",
-                                mutability,
-                            },
-                        );
+                reports.push(Report {
+                    target,
+                    summary: Summary::of(&diagnostics),
+                    suppressed: found - diagnostics.len(),
+                    diagnostics,
+                    unreached: validator.unreached_definitions(),
+                    undefined: validator.undefined_scopes(),
+                });
+            }
+
+            match format {
+                OutputFormat::Human => {
+                    for report in &reports {
+                        if targets.len() > 1 {
+                            eprintln!("Checking {}...", report.target.name);
+                        }
+
+                        if !report.unreached.is_empty() {
+                            eprintln!(
+                                "Note: the following scopes are defined but never reached..."
+                            );
+                            for scope in &report.unreached {
+                                eprintln!(" - {} defined at {}", scope.name, scope.defined_at);
+                            }
+                            eprintln!();
+                        }
+
+                        if !report.undefined.is_empty() {
+                            eprintln!(
+                                "Warning: the following scopes have no definition in the binary, \
+                                 which may happen if they were built against another version of \
+                                 saddle..."
+                            );
+                            for scope in &report.undefined {
+                                eprintln!(" - {} defined at {}", scope.name, scope.defined_at);
+                            }
+                            eprintln!();
+                        }
+
+                        if report.suppressed > 0 {
+                            eprintln!(
+                                "Note: {} known conflict(s) were suppressed by the baseline.\n",
+                                report.suppressed,
+                            );
+                        }
+
+                        if report.diagnostics.is_empty() {
+                            eprintln!("Binary is valid.");
+                        } else {
+                            for diagnostic in &report.diagnostics {
+                                if diagnostic.severity == Severity::Warning {
+                                    eprint!("Warning: ");
+                                }
+
+                                eprintln!("{diagnostic}\n");
+                            }
+
+                            eprintln!("{}", report.summary);
+                        }
+
+                        if targets.len() > 1 {
+                            eprintln!();
+                        }
                     }
-                    DecoderEntryKind::Grant(mutability) => {
-                        validator.annotate_component(
-                            ComponentId(arg_2.clone(), []),
-                            ComponentMeta {
-                                _dummy: [],
-                                name: arg_2.clone(),
-                            },
+                }
+                OutputFormat::Json => {
+                    // Each binary is reported as its own JSON document.
+                    for report in &reports {
+                        println!(
+                            "{}",
+                            serde_json::to_string_pretty(&serde_json::json!({
+                                "binary": report.target.name,
+                                "diagnostics": report.diagnostics,
+                                "suppressed": report.suppressed,
+                                "unreached_scopes": report.unreached,
+                                "undefined_scopes": report.undefined,
+                                "summary": report.summary,
+                            }))?,
                         );
+                    }
+                }
+                OutputFormat::Sarif => {
+                    let runs = reports
+                        .iter()
+                        .map(|report| (report.target.name.as_str(), &report.diagnostics[..]))
+                        .collect::<Vec<_>>();
+
+                    println!("{}", serde_json::to_string_pretty(&to_sarif(&runs))?);
+                }
+            }
+
+            // Conflicts which were fixed should be removed from the baseline so that they can't
+            // silently regress.
+            if let Some(baseline) = &baseline {
+                let stale = baseline.stale_entries(&all_entries);
 
-                        validator.push_grant(
-                            ScopeId(arg_1, []),
-                            ComponentId(arg_2, []),
-                            mutability,
-                            BorrowMeta {
-                                def_path: "<unknown>",
-                                mutability,
-                            },
+                if !stale.is_empty() {
+                    eprintln!("Warning: the following baseline entries no longer occur and can be removed...");
+                    for entry in stale {
+                        eprintln!(
+                            " - {} borrowing {} {}",
+                            entry.scope,
+                            entry.component,
+                            entry.mutability.adjective(),
                         );
                     }
-                    DecoderEntryKind::Calls => validator.push_call_edge(
-                        ScopeId(arg_1, []),
-                        ScopeId(arg_2, []),
-                        CallMeta {
-                            def_path: "<unknown>",
-                        },
-                    ),
                 }
-            })?;
+            }
+
+            if !unmatched_roots.is_empty() {
+                eprintln!(
+                    "Warning: the following roots configured in {CONFIG_FILE} match no scope: {}",
+                    unmatched_roots.join(", "),
+                );
+            }
+
+            // Machine-readable reports are printed in full but should still fail CI. Lints
+            // configured as warnings are reported without failing.
+            let summary = reports.iter().map(|report| report.summary).sum::<Summary>();
 
             anyhow::ensure!(
-                found_any_directive,
-                "Did not find any saddle directives while scanning binary."
+                !reports
+                    .iter()
+                    .flat_map(|report| &report.diagnostics)
+                    .any(|diagnostic| diagnostic.severity == Severity::Error),
+                "Failed to validate the scope graph. {summary}",
+            );
+
+            Ok(())
+        }
+        Commands::Baseline { binary, output } => {
+            let targets = load_targets(binary, &config)?;
+            let output = output
+                .as_deref()
+                .map(PathBuf::from)
+                .or_else(|| config.baseline.clone())
+                .unwrap_or_else(|| PathBuf::from("saddle-baseline.json"));
+
+            let mut entries = Vec::new();
+
+            for target in &targets {
+                let validator = build_validator(target, &config)?;
+                let keys = validator.scope_keys();
+                entries.extend(
+                    validator
+                        .validate()
+                        .iter()
+                        .map(|diagnostic| BaselineEntry::of(diagnostic, &keys)),
+                );
+            }
+
+            let baseline = Baseline::of(entries);
+            baseline.save(&output)?;
+
+            eprintln!(
+                "Wrote {} known conflict(s) to {}.",
+                baseline.conflicts.len(),
+                output.display(),
             );
+            Ok(())
+        }
+        Commands::Graph {
+            binary,
+            format,
+            from,
+            to,
+            component,
+            output,
+        } => {
+            let target = load_single_target(binary, &config)?;
+            let validator = build_validator(&target, &config)?;
 
-            // Validate graph
-            validator.validate()?;
+            let graph = validator.export_graph(&GraphFilter {
+                reachable_from: from.as_deref(),
+                reaching: to.as_deref(),
+                component: component.as_deref(),
+            })?;
+
+            let rendered = match format {
+                GraphFormat::Dot => graph::to_dot(&graph),
+                GraphFormat::Mermaid => graph::to_mermaid(&graph),
+                GraphFormat::Graphml => graph::to_graphml(&graph),
+            };
 
-            eprintln!("Binary is valid.");
+            match output {
+                Some(output) => {
+                    fs::write(output, rendered).context("failed to write graph to output file")?
+                }
+                None => print!("{rendered}"),
+            }
+
+            Ok(())
+        }
+        Commands::Why {
+            binary,
+            scope,
+            component,
+            top,
+        } => {
+            let target = load_single_target(binary, &config)?;
+            let validator = build_validator(&target, &config)?;
+            let chains = validator.why(scope, component, *top)?;
+
+            print!("{}", why::render_chains(scope, component, &chains));
             Ok(())
         }
+        Commands::Diff {
+            old,
+            new,
+            format,
+            attribution,
+        } => {
+            let old = load_path_target(old, attribution.attribution())?;
+            let new = load_path_target(new, attribution.attribution())?;
+
+            let diff = GraphDiff::between(
+                &build_validator(&old, &config)?.snapshot(),
+                &build_validator(&new, &config)?.snapshot(),
+            );
+
+            match format {
+                DiffFormat::Human => print!("{diff}"),
+                DiffFormat::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+            }
+
+            Ok(())
+        }
+    }
+}
+
+/// Loads the binary at the provided path or, if none was provided, builds the current package and
+/// loads every binary it produced which uses saddle.
+fn load_targets(args: &BinaryArgs, config: &Config) -> anyhow::Result<Vec<Target>> {
+    let Some(path) = &args.path else {
+        let mut targets = Vec::new();
+
+        let harnesses_only = args.build.tests || args.build.benches;
+
+        let mut cargo_args = args.build.to_cargo_args();
+
+        // The configured binaries apply unless the targets to build were chosen explicitly.
+        let build = &args.build;
+        if build.package.is_empty() && build.bin.is_empty() && build.example.is_empty() {
+            for binary in &config.binaries {
+                cargo_args.extend(["--bin".to_string(), binary.clone()]);
+            }
+        }
+
+        for artifact in cargo::build(&cargo_args)? {
+            // Building harnesses also builds the binaries they test, which we skip.
+            if harnesses_only && !artifact.harness {
+                continue;
+            }
+
+            // Binaries which do not use saddle may still contain strings resembling our markers
+            // (e.g. cargo-saddle itself) so we don't let them fail the entire run.
+            let binary = match load_binary(&artifact.path, args.attribution()) {
+                Ok(binary) => binary,
+                Err(err) => {
+                    eprintln!(
+                        "Note: skipping {} since it could not be decoded: {err}",
+                        artifact.name
+                    );
+                    continue;
+                }
+            };
+
+            if binary.entries.is_empty() && binary.definitions.is_empty() {
+                eprintln!(
+                    "Note: skipping {} since it does not use saddle.",
+                    artifact.name
+                );
+                continue;
+            }
+
+            let entry_points = if artifact.harness {
+                find_test_entry_points(&artifact.path, &binary)?
+            } else {
+                Vec::new()
+            };
+
+            targets.push(Target {
+                name: artifact.name,
+                binary,
+                entry_points,
+            });
+        }
+
+        anyhow::ensure!(
+            !targets.is_empty(),
+            "Did not find any saddle directives in the binaries produced by the build."
+        );
+
+        return Ok(targets);
+    };
+
+    Ok(vec![load_path_target(path, args.attribution())?])
+}
+
+/// Loads the binary at the provided path, which must contain saddle directives.
+fn load_path_target(path: &str, attribution: Option<Attribution>) -> anyhow::Result<Target> {
+    let binary = load_binary(path, attribution)?;
+
+    anyhow::ensure!(
+        !binary.entries.is_empty() || !binary.definitions.is_empty(),
+        "Did not find any saddle directives while scanning {path}."
+    );
+
+    Ok(Target {
+        name: path.to_string(),
+        binary,
+        entry_points: Vec::new(),
+    })
+}
+
+/// Like [`load_targets`] but for commands which analyze a single binary.
+fn load_single_target(args: &BinaryArgs, config: &Config) -> anyhow::Result<Target> {
+    let mut targets = load_targets(args, config)?;
+
+    anyhow::ensure!(
+        targets.len() == 1,
+        "The build produced {} binaries using saddle ({}). Select one with --bin, --example or \
+         --package.",
+        targets.len(),
+        targets
+            .iter()
+            .map(|target| target.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", "),
+    );
+
+    Ok(targets.pop().unwrap())
+}
+
+fn load_binary(path: &str, attribution: Option<Attribution>) -> anyhow::Result<DecodedBinary> {
+    // Load the file
+    let file = fs::read(path).context("failed to read file to be analyzed")?;
+
+    // Load all declarations
+    let mut binary = decode_binary(&file)?;
+
+    if let Some(attribution) = attribution {
+        elf::resolve_references(&file, &mut binary, attribution)?;
+    }
+
+    Ok(binary)
+}
+
+/// Determines the scopes entered by each test of a harness executable. Each test is treated as an
+/// independent entry point so that conflicts can be traced back to the tests reaching them.
+fn find_test_entry_points(
+    path: &str,
+    binary: &DecodedBinary,
+) -> anyhow::Result<Vec<(String, String)>> {
+    let tests = cargo::list_tests(path)?
+        .into_iter()
+        .collect::<FxHashSet<_>>();
+
+    // Test names omit the name of the crate and the harness runs them through closures.
+    let test_of = |function: &str| {
+        let mut function = function;
+        while let Some(parent) = function.strip_suffix("::{{closure}}") {
+            function = parent;
+        }
+
+        let (_crate_name, path) = function.split_once("::")?;
+        tests.get(path).cloned()
+    };
+
+    let file = fs::read(path).context("failed to read file to be analyzed")?;
+    let reached_by = elf::find_entry_points(&file, binary, test_of)?;
+
+    let mut entry_points = Vec::new();
+
+    for (entry, tests) in binary.entries.iter().zip(reached_by) {
+        let entered = match entry.kind {
+            DecoderEntryKind::Calls | DecoderEntryKind::Dispatches | DecoderEntryKind::Spawns => {
+                &[&entry.arg_1, &entry.arg_2][..]
+            }
+            _ => &[&entry.arg_1][..],
+        };
+
+        for scope in entered {
+            for test in &tests {
+                entry_points.push((scope.to_string(), test.clone()));
+            }
+        }
     }
+
+    Ok(entry_points)
+}
+
+fn build_validator<'a>(target: &'a Target, config: &Config) -> anyhow::Result<Validator<'a>> {
+    let binary = &target.binary;
+    let mut validator = Validator::new(config);
+
+    // Register every scope up front so that references to scopes we know nothing about can be
+    // reported as such.
+    for definition in binary.definitions.values() {
+        validator.register_definition(&definition.name, &definition.defined_at);
+    }
+
+    for (id, scope) in &binary.scopes {
+        validator.register_scope(
+            ScopeId(id.clone(), []),
+            ScopeMeta {
+                _dummy: [],
+                name: scope.name.clone(),
+                defined_at: scope.defined_at.as_deref().unwrap_or(UNKNOWN_LOCATION),
+            },
+        );
+    }
+
+    for entry in &binary.entries {
+        let scope_1 = ScopeId(entry.arg_1.to_string(), []);
+        let arg_2 = entry.arg_2.to_string();
+
+        // Entries attributed to several functions are recorded once per function.
+        let def_paths = match (&entry.declared_at, &entry.referenced_by[..]) {
+            (Some(declared_at), _) => vec![declared_at.as_str()],
+            (None, []) => vec![UNKNOWN_LOCATION],
+            (None, functions) => functions.iter().map(String::as_str).collect(),
+        };
+
+        if let DecoderEntryKind::Dep(_)
+        | DecoderEntryKind::TransientDep(_)
+        | DecoderEntryKind::Grant(_)
+        | DecoderEntryKind::Hold(_) = entry.kind
+        {
+            validator.annotate_component(
+                ComponentId(arg_2.clone(), []),
+                ComponentMeta {
+                    _dummy: [],
+                    name: arg_2.clone(),
+                },
+            );
+        }
+
+        for def_path in def_paths {
+            match entry.kind {
+                DecoderEntryKind::Dep(mutability) => validator.push_access(
+                    &scope_1,
+                    ComponentId(arg_2.clone(), []),
+                    mutability,
+                    BorrowMeta {
+                        def_path,
+                        mutability,
+                    },
+                )?,
+                DecoderEntryKind::TransientDep(mutability) => validator.push_transient_access(
+                    &scope_1,
+                    ComponentId(arg_2.clone(), []),
+                    mutability,
+                    BorrowMeta {
+                        def_path,
+                        mutability,
+                    },
+                )?,
+                DecoderEntryKind::Grant(mutability) => validator.push_grant(
+                    &scope_1,
+                    ComponentId(arg_2.clone(), []),
+                    mutability,
+                    BorrowMeta {
+                        def_path,
+                        mutability,
+                    },
+                )?,
+                DecoderEntryKind::Hold(mutability) => validator.push_hold(
+                    &scope_1,
+                    ComponentId(arg_2.clone(), []),
+                    mutability,
+                    BorrowMeta {
+                        def_path,
+                        mutability,
+                    },
+                )?,
+                DecoderEntryKind::TaskRoot => validator.push_task_root(&scope_1)?,
+                DecoderEntryKind::Calls => validator.push_call_edge(
+                    &scope_1,
+                    &ScopeId(arg_2.clone(), []),
+                    CallMeta {
+                        def_path,
+                        held: entry.held.as_deref(),
+                    },
+                )?,
+                DecoderEntryKind::Dispatches => validator.push_dispatch_edge(
+                    &scope_1,
+                    &ScopeId(arg_2.clone(), []),
+                    CallMeta {
+                        def_path,
+                        held: None,
+                    },
+                )?,
+                DecoderEntryKind::Implements => {
+                    validator.push_impl(&scope_1, &ScopeId(arg_2.clone(), []))?
+                }
+                DecoderEntryKind::Spawns => validator.push_spawn_edge(
+                    &scope_1,
+                    &ScopeId(arg_2.clone(), []),
+                    CallMeta {
+                        def_path,
+                        held: None,
+                    },
+                )?,
+            }
+        }
+    }
+
+    for (scope, test) in &target.entry_points {
+        validator.push_entry_point(&ScopeId(scope.clone(), []), test)?;
+    }
+
+    Ok(validator)
 }
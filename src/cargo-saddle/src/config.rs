@@ -0,0 +1,137 @@
+//! The `saddle.toml` project configuration, which lives next to the workspace's `Cargo.toml`.
+
+use std::{fs, path::PathBuf};
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::{
+    cargo,
+    cli::OutputFormat,
+    diagnostic::{ConflictKind, Severity},
+};
+
+pub const CONFIG_FILE: &str = "saddle.toml";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// The scopes from which the program is entered. If any are listed, only scopes reachable
+    /// from them are checked.
+    pub roots: Vec<String>,
+    /// Components which are never checked, such as purely logical tags.
+    pub ignored_components: Vec<String>,
+    /// The severity of each lint.
+    pub lints: Lints,
+    /// The binaries checked when no path or build flags are given.
+    pub binaries: Vec<String>,
+    /// The default format in which diagnostics are reported.
+    pub format: Option<OutputFormat>,
+    /// The default baseline file, relative to the workspace root.
+    pub baseline: Option<PathBuf>,
+}
+
+#[derive(Debug, Copy, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Lints {
+    pub borrow_conflict: Severity,
+    pub reentrant_borrow: Severity,
+    pub concurrent_access: Severity,
+    pub suspended_borrow: Severity,
+}
+
+impl Lints {
+    pub fn severity_of(&self, kind: ConflictKind) -> Severity {
+        match kind {
+            ConflictKind::BorrowConflict => self.borrow_conflict,
+            ConflictKind::ReentrantBorrow => self.reentrant_borrow,
+            ConflictKind::ConcurrentAccess => self.concurrent_access,
+            ConflictKind::SuspendedBorrow => self.suspended_borrow,
+        }
+    }
+}
+
+impl Config {
+    /// Loads the `saddle.toml` at the root of the current workspace, falling back to the default
+    /// configuration if there is none.
+    pub fn discover() -> anyhow::Result<Self> {
+        let Some(root) = cargo::workspace_root() else {
+            return Ok(Self::default());
+        };
+
+        let path = root.join(CONFIG_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let file = fs::read_to_string(&path).context("failed to read saddle.toml")?;
+        let mut config = toml::from_str::<Self>(&file)
+            .with_context(|| format!("malformed configuration in {}", path.display()))?;
+
+        if let Some(baseline) = &mut config.baseline {
+            *baseline = root.join(&*baseline);
+        }
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn documented_configuration_is_parsed() {
+        let config = toml::from_str::<Config>(
+            r#"
+            roots = ["MainScope"]
+            ignored-components = ["RenderTag"]
+            binaries = ["game", "server"]
+            format = "human"
+            baseline = "saddle-baseline.json"
+
+            [lints]
+            borrow-conflict = "error"
+            reentrant-borrow = "warning"
+            concurrent-access = "error"
+            suspended-borrow = "allow"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.roots, ["MainScope"]);
+        assert_eq!(config.ignored_components, ["RenderTag"]);
+        assert_eq!(config.binaries, ["game", "server"]);
+        assert!(matches!(config.format, Some(OutputFormat::Human)));
+        assert_eq!(
+            config.baseline.as_deref(),
+            Some("saddle-baseline.json".as_ref()),
+        );
+        assert_eq!(
+            config.lints.severity_of(ConflictKind::ReentrantBorrow),
+            Severity::Warning,
+        );
+        assert_eq!(
+            config.lints.severity_of(ConflictKind::SuspendedBorrow),
+            Severity::Allow,
+        );
+    }
+
+    #[test]
+    fn omitted_settings_use_defaults() {
+        let config = toml::from_str::<Config>("[lints]\nreentrant-borrow = \"allow\"").unwrap();
+
+        assert!(config.roots.is_empty());
+        assert!(config.format.is_none());
+        assert_eq!(
+            config.lints.severity_of(ConflictKind::BorrowConflict),
+            Severity::Error,
+        );
+    }
+
+    #[test]
+    fn unknown_settings_are_rejected() {
+        assert!(toml::from_str::<Config>("root = [\"MainScope\"]").is_err());
+        assert!(toml::from_str::<Config>("[lints]\nborrow-conflicts = \"allow\"").is_err());
+    }
+}
@@ -1,27 +1,199 @@
+use anyhow::Context;
 use bstr::ByteSlice;
+use rustc_hash::FxHashMap;
 
-use crate::validator::Mutability;
+use crate::{
+    type_name::{GenericArg, Parser, Ty},
+    validator::Mutability,
+};
 
-const PREFIX: &'static str = "SaddleInternalV1DeclFor";
-const SUFFIX_DEP_REF: &'static str = "DepRef";
-const SUFFIX_DEP_MUT: &'static str = "DepMut";
-const SUFFIX_GRANT_REF: &'static str = "GrantRef";
-const SUFFIX_GRANT_MUT: &'static str = "GrantMut";
-const SUFFIX_CALLS: &'static str = "Call";
+const PREFIX: &str = "SaddleInternalV";
+const VERSION_1: &str = "1DeclFor";
+const VERSION_2: &str = "2DeclFor";
+const FILE_PREFIX: &str = "SaddleInternalV2File:";
+const SCOPE_PREFIX: &str = "SaddleInternalV2Scope:";
+const SUFFIX_DEP_REF: &str = "DepRef";
+const SUFFIX_DEP_MUT: &str = "DepMut";
+const SUFFIX_DEP_TRANSIENT_REF: &str = "DepTransientRef";
+const SUFFIX_DEP_TRANSIENT_MUT: &str = "DepTransientMut";
+const SUFFIX_GRANT_REF: &str = "GrantRef";
+const SUFFIX_GRANT_MUT: &str = "GrantMut";
+const SUFFIX_CALLS: &str = "Call";
+const SUFFIX_CALLS_HOLDING: &str = "CallHolding";
+const SUFFIX_DISPATCH: &str = "Dispatch";
+const SUFFIX_IMPL: &str = "Impl";
+const SUFFIX_SPAWN: &str = "Spawn";
+const SUFFIX_HOLD_REF: &str = "HoldRef";
+const SUFFIX_HOLD_MUT: &str = "HoldMut";
+const SUFFIX_TASK_ROOT: &str = "TaskRoot";
 
-const MALFORMED_SADDLE_MARKER_ERR: &'static str = "Malformed Saddle marker in binary";
+const MALFORMED_SADDLE_MARKER_ERR: &str = "Malformed Saddle marker in binary";
 
 #[derive(Debug, Copy, Clone)]
 pub enum DecoderEntryKind {
     Dep(Mutability),
+    /// A dependency which is released before the scope calls into any other scope.
+    TransientDep(Mutability),
     Grant(Mutability),
+    /// A dependency which may be held while the scope's async task is suspended.
+    Hold(Mutability),
     Calls,
+    Dispatches,
+    Implements,
+    Spawns,
+    TaskRoot,
 }
 
-pub fn decode_binary(
-    data: &[u8],
-    mut handler: impl FnMut(DecoderEntryKind, String, String),
-) -> anyhow::Result<()> {
+#[derive(Debug, Clone)]
+pub struct DecoderEntry {
+    pub kind: DecoderEntryKind,
+    pub arg_1: Ty,
+    pub arg_2: Ty,
+    /// The `file:line:column` at which this entry was declared. This is only known for entries
+    /// encoded using the V2 format.
+    pub declared_at: Option<String>,
+    /// The components which the caller keeps borrowed during a call declared with
+    /// `decl_call_holding`. This is `None` for every other entry.
+    pub held: Option<Vec<String>>,
+    /// The offset of the marker within the binary.
+    pub offset: usize,
+    /// The demangled names of the functions which reference the marker. This is only populated by
+    /// [`crate::elf::resolve_references`].
+    pub referenced_by: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DecodedScope {
+    /// The user-facing name of the scope's type, as opposed to that of its disambiguator.
+    pub name: String,
+    /// The `file:line:column` at which the scope was defined.
+    pub defined_at: Option<String>,
+}
+
+/// A scope definition emitted by the `scope!` macro, regardless of whether the scope is ever used.
+#[derive(Debug, Clone)]
+pub struct DecodedDefinition {
+    /// The name of the scope as written in its definition, including its generic parameters.
+    pub name: String,
+    /// The `file:line:column` at which the scope was defined.
+    pub defined_at: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DecodedBinary {
+    pub entries: Vec<DecoderEntry>,
+    /// Every scope mentioned by an entry, keyed by the canonical name of its disambiguator.
+    pub scopes: FxHashMap<String, DecodedScope>,
+    /// Every scope definition in the binary, keyed by its `defined_at` location. Binaries built
+    /// with versions of saddle predating definition markers have none.
+    pub definitions: FxHashMap<String, DecodedDefinition>,
+}
+
+/// Must be kept in sync with `saddle::scope_macro_internals::hash_file_path`.
+fn hash_file_path(path: &str) -> u64 {
+    let mut hash = 0xcbf29ce484222325_u64;
+
+    for &byte in path.as_bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    hash
+}
+
+/// Formats the `FILE, LINE, COLUMN` const generics trailing a `ScopeDisambiguator` or
+/// `SourceLocation` as a `file:line:column` string. Disambiguators emitted before file hashes were
+/// introduced only have the line and column.
+fn parse_location(args: &[GenericArg], files: &FxHashMap<u64, String>) -> Option<String> {
+    let mut consts = args.iter().rev().map_while(GenericArg::as_const);
+
+    let column = consts.next()?.parse::<u32>().ok()?;
+    let line = consts.next()?.parse::<u32>().ok()?;
+    let file = consts
+        .next()
+        .and_then(|hash| hash.parse::<u64>().ok())
+        .and_then(|hash| files.get(&hash))
+        .map_or("<unknown>", String::as_str);
+
+    Some(format!("{file}:{line}:{column}"))
+}
+
+fn decode_scope(ty: &Ty, files: &FxHashMap<u64, String>) -> DecodedScope {
+    let disamb = ty
+        .as_path()
+        .map(|path| path.last())
+        .filter(|segment| segment.name == "ScopeDisambiguator");
+
+    let Some(disamb) = disamb else {
+        return DecodedScope {
+            name: ty.to_string(),
+            defined_at: None,
+        };
+    };
+
+    let args = disamb.generic_args();
+
+    DecodedScope {
+        name: args
+            .first()
+            .and_then(GenericArg::as_ty)
+            .map_or_else(|| ty.to_string(), Ty::to_string),
+        defined_at: parse_location(args, files),
+    }
+}
+
+/// Iterates over the NUL-terminated payloads of every marker starting with `prefix`.
+fn find_string_markers<'a>(
+    data: &'a [u8],
+    prefix: &'a str,
+) -> impl Iterator<Item = anyhow::Result<&'a str>> + 'a {
+    memchr::memmem::find_iter(data, prefix.as_bytes()).map(move |start_offset| {
+        let cursor = &data[start_offset..][prefix.len()..];
+        let end = memchr::memchr(0, cursor).context(MALFORMED_SADDLE_MARKER_ERR)?;
+
+        cursor[..end]
+            .to_str()
+            .ok()
+            .context(MALFORMED_SADDLE_MARKER_ERR)
+    })
+}
+
+pub fn decode_binary(data: &[u8]) -> anyhow::Result<DecodedBinary> {
+    let mut binary = DecodedBinary::default();
+
+    // Collect the paths of every source file referenced by a V2 marker.
+    let mut files = FxHashMap::default();
+
+    for path in find_string_markers(data, FILE_PREFIX) {
+        let path = path?;
+        files.insert(hash_file_path(path), path.to_string());
+    }
+
+    // Collect every scope definition. These have the form `name:file:line:column` where the name
+    // cannot contain colons but the file can.
+    for definition in find_string_markers(data, SCOPE_PREFIX) {
+        let (name, defined_at) = definition?
+            .split_once(':')
+            .context(MALFORMED_SADDLE_MARKER_ERR)?;
+
+        let mut location = defined_at.rsplitn(3, ':');
+        let (Some(_column), Some(_line), Some(path)) =
+            (location.next(), location.next(), location.next())
+        else {
+            anyhow::bail!("{MALFORMED_SADDLE_MARKER_ERR}");
+        };
+
+        files.insert(hash_file_path(path), path.to_string());
+        binary.definitions.insert(
+            defined_at.to_string(),
+            DecodedDefinition {
+                name: name.to_string(),
+                defined_at: defined_at.to_string(),
+            },
+        );
+    }
+
+    // Collect the markers themselves.
     let mut min_offset = 0;
 
     for start_offset in memchr::memmem::find_iter(data, PREFIX.as_bytes()) {
@@ -30,71 +202,215 @@ pub fn decode_binary(
             continue;
         }
 
-        // Parse mode of type we encountered.
+        // Parse the version of the marker we encountered. The file and scope definition markers
+        // share our prefix so we skip over them.
         let mut cursor = &data[start_offset..][PREFIX.len()..];
 
+        let has_location = if cursor.starts_with(VERSION_1.as_bytes()) {
+            cursor = &cursor[VERSION_1.len()..];
+            false
+        } else if cursor.starts_with(VERSION_2.as_bytes()) {
+            cursor = &cursor[VERSION_2.len()..];
+            true
+        } else {
+            continue;
+        };
+
+        // Parse mode of type we encountered. Calls declaring the components they hold carry an extra
+        // type argument listing them.
+        let mut has_held = false;
+
         let kind = if cursor.starts_with(SUFFIX_DEP_REF.as_bytes()) {
             cursor = &cursor[SUFFIX_DEP_REF.len()..];
             DecoderEntryKind::Dep(Mutability::Immutable)
         } else if cursor.starts_with(SUFFIX_DEP_MUT.as_bytes()) {
             cursor = &cursor[SUFFIX_DEP_MUT.len()..];
             DecoderEntryKind::Dep(Mutability::Mutable)
+        } else if cursor.starts_with(SUFFIX_DEP_TRANSIENT_REF.as_bytes()) {
+            cursor = &cursor[SUFFIX_DEP_TRANSIENT_REF.len()..];
+            DecoderEntryKind::TransientDep(Mutability::Immutable)
+        } else if cursor.starts_with(SUFFIX_DEP_TRANSIENT_MUT.as_bytes()) {
+            cursor = &cursor[SUFFIX_DEP_TRANSIENT_MUT.len()..];
+            DecoderEntryKind::TransientDep(Mutability::Mutable)
         } else if cursor.starts_with(SUFFIX_GRANT_REF.as_bytes()) {
             cursor = &cursor[SUFFIX_GRANT_REF.len()..];
             DecoderEntryKind::Grant(Mutability::Immutable)
         } else if cursor.starts_with(SUFFIX_GRANT_MUT.as_bytes()) {
             cursor = &cursor[SUFFIX_GRANT_MUT.len()..];
             DecoderEntryKind::Grant(Mutability::Mutable)
+        } else if cursor.starts_with(SUFFIX_CALLS_HOLDING.as_bytes()) {
+            cursor = &cursor[SUFFIX_CALLS_HOLDING.len()..];
+            has_held = true;
+            DecoderEntryKind::Calls
         } else if cursor.starts_with(SUFFIX_CALLS.as_bytes()) {
             cursor = &cursor[SUFFIX_CALLS.len()..];
             DecoderEntryKind::Calls
+        } else if cursor.starts_with(SUFFIX_DISPATCH.as_bytes()) {
+            cursor = &cursor[SUFFIX_DISPATCH.len()..];
+            DecoderEntryKind::Dispatches
+        } else if cursor.starts_with(SUFFIX_IMPL.as_bytes()) {
+            cursor = &cursor[SUFFIX_IMPL.len()..];
+            DecoderEntryKind::Implements
+        } else if cursor.starts_with(SUFFIX_SPAWN.as_bytes()) {
+            cursor = &cursor[SUFFIX_SPAWN.len()..];
+            DecoderEntryKind::Spawns
+        } else if cursor.starts_with(SUFFIX_HOLD_REF.as_bytes()) {
+            cursor = &cursor[SUFFIX_HOLD_REF.len()..];
+            DecoderEntryKind::Hold(Mutability::Immutable)
+        } else if cursor.starts_with(SUFFIX_HOLD_MUT.as_bytes()) {
+            cursor = &cursor[SUFFIX_HOLD_MUT.len()..];
+            DecoderEntryKind::Hold(Mutability::Mutable)
+        } else if cursor.starts_with(SUFFIX_TASK_ROOT.as_bytes()) {
+            cursor = &cursor[SUFFIX_TASK_ROOT.len()..];
+            DecoderEntryKind::TaskRoot
         } else {
             anyhow::bail!("{MALFORMED_SADDLE_MARKER_ERR}");
         };
 
         // Parse generics
-        let mut cursor = cursor.chars();
-
-        fn parse_ty(cursor: &mut bstr::Chars<'_>) -> anyhow::Result<String> {
-            let mut collector = String::new();
-
-            let mut generic_level = 0;
-            while let Some(char) = cursor.clone().next() {
-                if char == ' ' {
-                    let _ = cursor.next();
-                    continue;
-                } else if char == '<' {
-                    generic_level += 1;
-                    let _ = cursor.next();
-                } else if char == '>' {
-                    if generic_level > 0 {
-                        generic_level -= 1;
-                        let _ = cursor.next();
-                    } else {
-                        break;
-                    }
-                } else if char == ',' && generic_level == 0 {
-                    break;
-                } else {
-                    let _ = cursor.next();
-                }
-
-                collector.push(char);
-            }
-
-            Ok(collector)
+        let mut parser = Parser::new(cursor);
+        let args = parser
+            .parse_generic_args()
+            .context(MALFORMED_SADDLE_MARKER_ERR)?;
+
+        let mut args = args.into_iter();
+        let mut next_ty = || match args.next() {
+            Some(GenericArg::Type(ty)) => Ok(ty),
+            _ => Err(anyhow::anyhow!("{MALFORMED_SADDLE_MARKER_ERR}")),
+        };
+
+        let ty_1 = next_ty()?;
+        let ty_2 = next_ty()?;
+
+        let held = if has_held {
+            // Symbol names render single element tuples without their trailing comma.
+            Some(match next_ty()? {
+                Ty::Tuple(components) => components.iter().map(Ty::to_string).collect(),
+                Ty::Paren(component) => vec![component.to_string()],
+                component => vec![component.to_string()],
+            })
+        } else {
+            None
+        };
+
+        let declared_at = if has_location {
+            let loc = next_ty()?;
+            let loc = loc
+                .as_path()
+                .map(|path| path.last())
+                .filter(|segment| segment.name == "SourceLocation")
+                .and_then(|segment| parse_location(segment.generic_args(), &files));
+
+            Some(loc.context(MALFORMED_SADDLE_MARKER_ERR)?)
+        } else {
+            None
+        };
+
+        anyhow::ensure!(args.next().is_none(), "{MALFORMED_SADDLE_MARKER_ERR}");
+
+        // Record the scopes involved.
+        let scope_args = match kind {
+            DecoderEntryKind::Dep(_)
+            | DecoderEntryKind::TransientDep(_)
+            | DecoderEntryKind::Grant(_)
+            | DecoderEntryKind::Hold(_)
+            | DecoderEntryKind::TaskRoot => &[&ty_1][..],
+            DecoderEntryKind::Calls
+            | DecoderEntryKind::Dispatches
+            | DecoderEntryKind::Implements
+            | DecoderEntryKind::Spawns => &[&ty_1, &ty_2][..],
+        };
+
+        for &scope in scope_args {
+            binary
+                .scopes
+                .entry(scope.to_string())
+                .or_insert_with(|| decode_scope(scope, &files));
         }
 
-        anyhow::ensure!(cursor.next() == Some('<'), "{MALFORMED_SADDLE_MARKER_ERR}");
-        let ty_1 = parse_ty(&mut cursor)?;
-        anyhow::ensure!(cursor.next() == Some(','), "{MALFORMED_SADDLE_MARKER_ERR}");
-        let ty_2 = parse_ty(&mut cursor)?;
-        anyhow::ensure!(cursor.next() == Some('>'), "{MALFORMED_SADDLE_MARKER_ERR}");
+        binary.entries.push(DecoderEntry {
+            kind,
+            arg_1: ty_1,
+            arg_2: ty_2,
+            declared_at,
+            held,
+            offset: start_offset,
+            referenced_by: Vec::new(),
+        });
+
+        min_offset = data.len() - cursor.len() + parser.position();
+    }
+
+    Ok(binary)
+}
 
-        handler(kind, ty_1, ty_2);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        min_offset = data.len() - cursor.as_bytes().len();
+    fn decode_held(marker: &str) -> Option<Vec<String>> {
+        let binary = decode_binary(format!("\0{marker}\0").as_bytes()).unwrap();
+        assert_eq!(binary.entries.len(), 1);
+        binary.entries[0].held.clone()
     }
 
-    Ok(())
+    #[test]
+    fn held_components_are_decoded() {
+        assert_eq!(
+            decode_held(
+                "SaddleInternalV1DeclForCallHolding<app::Outer, app::Inner, (u32, app::World)>"
+            ),
+            Some(vec!["u32".to_string(), "app::World".to_string()]),
+        );
+        assert_eq!(
+            decode_held("SaddleInternalV1DeclForCallHolding<app::Outer, app::Inner, (u32)>"),
+            Some(vec!["u32".to_string()]),
+        );
+        assert_eq!(
+            decode_held("SaddleInternalV1DeclForCallHolding<app::Outer, app::Inner, ()>"),
+            Some(vec![]),
+        );
+        assert_eq!(
+            decode_held("SaddleInternalV1DeclForCall<app::Outer, app::Inner>"),
+            None,
+        );
+    }
+
+    #[test]
+    fn locations_are_decoded() {
+        let hash = hash_file_path("src/main.rs");
+        let data = format!(
+            "\0SaddleInternalV2File:src/main.rs\0\
+             \0SaddleInternalV2Scope:Physics:src/main.rs:3:1\0\
+             \0SaddleInternalV2DeclForDepMut<\
+             saddle::scope_macro_internals::ScopeDisambiguator<app::Physics, {hash}, 3, 1>, u32, \
+             saddle::scope_macro_internals::SourceLocation<{hash}, 7, 5>>\0",
+        );
+        let binary = decode_binary(data.as_bytes()).unwrap();
+
+        assert_eq!(binary.entries.len(), 1);
+        assert_eq!(
+            binary.entries[0].declared_at.as_deref(),
+            Some("src/main.rs:7:5"),
+        );
+
+        let scope = &binary.scopes[&binary.entries[0].arg_1.to_string()];
+        assert_eq!(scope.name, "app::Physics");
+        assert_eq!(scope.defined_at.as_deref(), Some("src/main.rs:3:1"));
+        assert_eq!(binary.definitions["src/main.rs:3:1"].name, "Physics");
+    }
+
+    #[test]
+    fn unknown_files_are_reported_as_unknown() {
+        let binary = decode_binary(
+            b"\0SaddleInternalV2DeclForDepRef<app::Physics, u32, \
+              saddle::scope_macro_internals::SourceLocation<42, 7, 5>>\0",
+        )
+        .unwrap();
+
+        assert_eq!(
+            binary.entries[0].declared_at.as_deref(),
+            Some("<unknown>:7:5"),
+        );
+    }
 }
@@ -0,0 +1,591 @@
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Write};
+
+use crate::validator::Mutability;
+
+// === Helpers === //
+
+pub const INDENT_SIZE: u32 = 4;
+
+/// The location reported for declarations whose location is not recorded in the binary.
+pub const UNKNOWN_LOCATION: &str = "<unknown>";
+
+#[derive(Copy, Clone)]
+pub struct Indent(pub u32);
+
+impl fmt::Display for Indent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for _ in 0..self.0 {
+            f.write_char(' ')?;
+        }
+        Ok(())
+    }
+}
+
+// === Model === //
+
+/// A single borrow conflict found by the [`Validator`](crate::validator::Validator).
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub kind: ConflictKind,
+    pub severity: Severity,
+    /// The scope performing the offending borrow.
+    pub scope: ScopeRef,
+    pub component: String,
+    /// The way in which `scope` borrows the component.
+    pub mutability: Mutability,
+    /// The way in which the component may already be held when `scope` is entered.
+    pub held_mutability: Mutability,
+    /// Why the component may already be held when `scope` is entered.
+    pub explanation: Responsibility,
+    /// The members of the cycle through which `scope` may be re-entered, if the conflict could be
+    /// caused by recursion.
+    pub cycle: Option<Vec<CycleMember>>,
+    /// Each of the calling contexts in which the conflict occurs. This is empty unless the
+    /// validator was run with a context depth.
+    pub contexts: Vec<CallingContext>,
+    /// The scope running concurrently with `scope` and the way in which both borrow the component,
+    /// if this is a [`ConflictKind::ConcurrentAccess`] or a [`ConflictKind::SuspendedBorrow`]. The
+    /// explanation is empty in that case.
+    pub concurrency: Option<Concurrency>,
+    /// The entry points (e.g. tests) from which the conflict may be reached. This is empty unless
+    /// entry points were registered with the validator.
+    pub reached_from: Vec<String>,
+}
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    /// The component may be held by a caller of the scope.
+    BorrowConflict,
+    /// The component may be held by the scope itself or by another member of a cycle it is part of.
+    ReentrantBorrow,
+    /// The component may be borrowed by another scope running on a different thread at the same
+    /// time.
+    ConcurrentAccess,
+    /// The component may be borrowed by another async task while the scope's task is suspended
+    /// holding it.
+    SuspendedBorrow,
+}
+
+impl ConflictKind {
+    pub fn rule_id(self) -> &'static str {
+        match self {
+            ConflictKind::BorrowConflict => "borrow-conflict",
+            ConflictKind::ReentrantBorrow => "reentrant-borrow",
+            ConflictKind::ConcurrentAccess => "concurrent-access",
+            ConflictKind::SuspendedBorrow => "suspended-borrow",
+        }
+    }
+}
+
+/// How a lint is reported, as configured in `saddle.toml`.
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// The lint is not reported.
+    Allow,
+    /// The lint is reported but does not fail the check.
+    Warning,
+    /// The lint is reported and fails the check.
+    #[default]
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScopeRef {
+    pub id: String,
+    pub name: String,
+    pub defined_at: String,
+}
+
+/// Identifies the scopes of a build in a way which is stable across builds. Scopes are keyed by
+/// name, except for those sharing their name with another scope of the build (e.g. the scopes of
+/// `scope!` blocks) which are also keyed by their location.
+#[derive(Debug, Clone, Default)]
+pub struct ScopeKeys {
+    ids_by_name: FxHashMap<String, FxHashSet<String>>,
+}
+
+impl ScopeKeys {
+    pub fn new<'s>(scopes: impl IntoIterator<Item = &'s ScopeRef>) -> Self {
+        let mut ids_by_name = FxHashMap::<String, FxHashSet<String>>::default();
+        for scope in scopes {
+            ids_by_name
+                .entry(scope.name.clone())
+                .or_default()
+                .insert(scope.id.clone());
+        }
+
+        Self { ids_by_name }
+    }
+
+    pub fn key(&self, scope: &ScopeRef) -> String {
+        if self
+            .ids_by_name
+            .get(&scope.name)
+            .is_some_and(|ids| ids.len() > 1)
+        {
+            format!("{} (defined at {})", scope.name, scope.defined_at)
+        } else {
+            scope.name.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BorrowSite {
+    pub mutability: Mutability,
+    pub location: String,
+}
+
+/// The reasons for which a scope may hold a given component, expressed as the set of scopes which
+/// may be responsible for it. Scopes shared between several call paths appear only once per calling
+/// context.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Responsibility {
+    /// The responsible scopes. The first node is always the scope being explained.
+    pub nodes: Vec<ResponsibilityNode>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResponsibilityNode {
+    pub scope: ScopeRef,
+    /// The places where the scope borrows the component itself.
+    pub borrowed_at: Vec<BorrowSite>,
+    /// The places where the scope grants the component, which limits the way in which its callers
+    /// may hold it.
+    pub granted_at: Vec<BorrowSite>,
+    /// The callers which may have called the scope while holding the component.
+    pub callers: Vec<ResponsibleCaller>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResponsibleCaller {
+    /// The index of the caller in [`Responsibility::nodes`].
+    pub node: usize,
+    pub held_mutability: Mutability,
+    pub call_sites: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CycleMember {
+    pub scope: ScopeRef,
+    pub calls: Vec<CycleCall>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CycleCall {
+    pub scope: ScopeRef,
+    pub call_site: String,
+}
+
+/// The most recent calls leading to a scope, outermost first.
+#[derive(Debug, Clone, Serialize)]
+pub struct CallingContext {
+    pub calls: Vec<ContextCall>,
+    /// Whether other calls may precede the first one, which were forgotten because of the context
+    /// depth.
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextCall {
+    pub caller: ScopeRef,
+    pub callee: ScopeRef,
+    pub call_site: String,
+}
+
+/// Why two scopes may run at the same time and how each of them borrows the conflicting component.
+#[derive(Debug, Clone, Serialize)]
+pub struct Concurrency {
+    /// The scope running concurrently with the diagnostic's scope.
+    pub other: ScopeRef,
+    /// The spawns which cause the two scopes to run at the same time.
+    pub spawned_at: Vec<SpawnSite>,
+    /// The async task which may be suspended in the diagnostic's scope while `other` runs. In that
+    /// case, `other` is the root of another task and there are no spawns.
+    pub suspended_task: Option<ScopeRef>,
+    /// The scopes borrowing the component while the diagnostic's scope runs.
+    pub accesses: Vec<TaskAccess>,
+    /// The scopes borrowing the component while `other` runs.
+    pub other_accesses: Vec<TaskAccess>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpawnSite {
+    pub spawner: ScopeRef,
+    pub spawned: ScopeRef,
+    pub location: String,
+}
+
+/// A scope which may borrow a component as part of a task, either itself or as one of the scopes
+/// it calls.
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskAccess {
+    pub scope: ScopeRef,
+    pub borrowed_at: Vec<BorrowSite>,
+}
+
+/// A scope which is defined in the binary but never referenced by any declaration.
+#[derive(Debug, Clone, Serialize)]
+pub struct UnreachedScope {
+    pub name: String,
+    pub defined_at: String,
+}
+
+/// Totals over a set of diagnostics.
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct Summary {
+    pub conflicts: usize,
+    pub scopes: usize,
+}
+
+impl Summary {
+    pub fn of(diagnostics: &[Diagnostic]) -> Self {
+        Self {
+            conflicts: diagnostics.len(),
+            scopes: diagnostics
+                .iter()
+                .map(|diagnostic| &diagnostic.scope.id)
+                .collect::<FxHashSet<_>>()
+                .len(),
+        }
+    }
+}
+
+/// Totals the summaries of several binaries. Scopes shared between binaries are counted once per
+/// binary.
+impl std::iter::Sum for Summary {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(
+            Self {
+                conflicts: 0,
+                scopes: 0,
+            },
+            |acc, summary| Self {
+                conflicts: acc.conflicts + summary.conflicts,
+                scopes: acc.scopes + summary.scopes,
+            },
+        )
+    }
+}
+
+// === Human Rendering === //
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Found {} conflicting borrow{} in {} scope{}.",
+            self.conflicts,
+            if self.conflicts == 1 { "" } else { "s" },
+            self.scopes,
+            if self.scopes == 1 { "" } else { "s" },
+        )
+    }
+}
+
+impl Diagnostic {
+    pub fn summary(&self) -> String {
+        if let Some(concurrency) = &self.concurrency {
+            return format!(
+                "The scope {} defined at {} borrows the component {} {} even though the scope {} \
+                 defined at {}, which may run {}, borrows it {}.",
+                self.scope.name,
+                self.scope.defined_at,
+                self.component,
+                self.mutability.adjective(),
+                concurrency.other.name,
+                concurrency.other.defined_at,
+                if concurrency.suspended_task.is_some() {
+                    "while it is suspended"
+                } else {
+                    "at the same time"
+                },
+                self.held_mutability.adjective(),
+            );
+        }
+
+        format!(
+            "The scope {} defined at {} borrows the component {} {} even though it may have already been borrowed {}.",
+            self.scope.name,
+            self.scope.defined_at,
+            self.component,
+            self.mutability.adjective(),
+            self.held_mutability.adjective(),
+        )
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.summary())?;
+
+        match &self.contexts[..] {
+            [] => {}
+            [context] => {
+                writeln!(
+                    f,
+                    "{}- This conflict occurs when the scope is entered through the following calls...",
+                    Indent(INDENT_SIZE),
+                )?;
+                context.fmt_tree(f, INDENT_SIZE * 2)?;
+            }
+            contexts => {
+                writeln!(
+                    f,
+                    "{}- This conflict occurs when the scope is entered in any of the following contexts...",
+                    Indent(INDENT_SIZE),
+                )?;
+
+                for context in contexts {
+                    writeln!(
+                        f,
+                        "{}- Through the following calls...",
+                        Indent(INDENT_SIZE * 2)
+                    )?;
+                    context.fmt_tree(f, INDENT_SIZE * 3)?;
+                }
+            }
+        }
+
+        self.explanation.fmt_tree(f, INDENT_SIZE)?;
+
+        if let Some(cycle) = &self.cycle {
+            writeln!(
+                f,
+                "{}- This scope may be re-entered while the component is held because the \
+                 following scopes may call one another in a cycle...",
+                Indent(INDENT_SIZE),
+            )?;
+
+            for member in cycle {
+                writeln!(
+                    f,
+                    "{}- Scope {}, which could call into...",
+                    Indent(INDENT_SIZE * 2),
+                    member.scope.name,
+                )?;
+
+                for call in &member.calls {
+                    writeln!(
+                        f,
+                        "{}- Scope {} through the invocation declared at {}",
+                        Indent(INDENT_SIZE * 3),
+                        call.scope.name,
+                        call.call_site,
+                    )?;
+                }
+            }
+        }
+
+        if let Some(concurrency) = &self.concurrency {
+            concurrency.fmt_tree(f, &self.scope, INDENT_SIZE)?;
+        }
+
+        if !self.reached_from.is_empty() {
+            writeln!(
+                f,
+                "{}- This conflict may be reached from the following entry points...",
+                Indent(INDENT_SIZE),
+            )?;
+
+            for entry_point in &self.reached_from {
+                writeln!(f, "{}- {entry_point}", Indent(INDENT_SIZE * 2))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl CallingContext {
+    /// Lists the calls forming the context, each at the given indentation.
+    fn fmt_tree(&self, f: &mut fmt::Formatter<'_>, indent: u32) -> fmt::Result {
+        if self.truncated {
+            writeln!(f, "{}- (earlier calls are omitted)", Indent(indent))?;
+        }
+
+        if self.calls.is_empty() {
+            writeln!(f, "{}- (the scope is entered first)", Indent(indent))?;
+        }
+
+        for call in &self.calls {
+            writeln!(
+                f,
+                "{}- The scope {} calls {} through the invocation declared at {}",
+                Indent(indent),
+                call.caller.name,
+                call.callee.name,
+                call.call_site,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Concurrency {
+    fn fmt_tree(&self, f: &mut fmt::Formatter<'_>, scope: &ScopeRef, indent: u32) -> fmt::Result {
+        if let Some(task) = &self.suspended_task {
+            writeln!(
+                f,
+                "{}- The task {} may be suspended in this scope while holding the component, \
+                 letting the task {} run in the meantime.",
+                Indent(indent),
+                task.name,
+                self.other.name,
+            )?;
+        } else {
+            writeln!(
+                f,
+                "{}- These scopes may run at the same time because of the following spawns...",
+                Indent(indent),
+            )?;
+        }
+
+        for site in &self.spawned_at {
+            writeln!(
+                f,
+                "{}- The scope {} spawns {} at {}",
+                Indent(indent + INDENT_SIZE),
+                site.spawner.name,
+                site.spawned.name,
+                site.location,
+            )?;
+        }
+
+        for (i, (task, accesses)) in [(scope, &self.accesses), (&self.other, &self.other_accesses)]
+            .into_iter()
+            .enumerate()
+        {
+            if i == 0 && self.suspended_task.is_some() {
+                writeln!(
+                    f,
+                    "{}- While the task is suspended, the component is held by...",
+                    Indent(indent),
+                )?;
+            } else {
+                writeln!(
+                    f,
+                    "{}- While {} runs, the component is borrowed by...",
+                    Indent(indent),
+                    task.name,
+                )?;
+            }
+
+            for access in accesses {
+                for site in &access.borrowed_at {
+                    writeln!(
+                        f,
+                        "{}- The scope {}, which borrows it {} at location {}.",
+                        Indent(indent + INDENT_SIZE),
+                        access.scope.name,
+                        site.mutability.adjective(),
+                        site.location,
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Responsibility {
+    fn fmt_tree(&self, f: &mut fmt::Formatter<'_>, indent: u32) -> fmt::Result {
+        for (i, node) in self.nodes.iter().enumerate() {
+            // The scope being explained is described by the diagnostic's summary. Every other
+            // scope is labeled so that the callers it is responsible for can refer back to it.
+            let indent = if i == 0 {
+                indent
+            } else {
+                writeln!(
+                    f,
+                    "{}[{i}] The scope {} defined at {}...",
+                    Indent(indent),
+                    node.scope.name,
+                    node.scope.defined_at,
+                )?;
+                indent + INDENT_SIZE
+            };
+
+            // We begin by logging out the direct borrows.
+            for site in &node.borrowed_at {
+                writeln!(
+                    f,
+                    "{}- This scope could have borrowed the component {} at location {}.",
+                    Indent(indent),
+                    site.mutability.adjective(),
+                    site.location,
+                )?;
+            }
+
+            for site in &node.granted_at {
+                writeln!(
+                    f,
+                    "{}- This scope grants the component {} at location {}, so its callers may \
+                     only hold it immutably when calling it.",
+                    Indent(indent),
+                    site.mutability.adjective(),
+                    site.location,
+                )?;
+            }
+
+            // Now, we log out indirect calls.
+            for caller in &node.callers {
+                let caller_scope = &self.nodes[caller.node].scope;
+
+                writeln!(
+                    f,
+                    "{}- The scope {} defined at {} may have called it while the component was held {}.\n\
+                     {}  Hint: the call was made through the following invocations...",
+                    Indent(indent),
+                    caller_scope.name,
+                    caller_scope.defined_at,
+                    caller.held_mutability.adjective(),
+                    Indent(indent),
+                )?;
+
+                // Calls declared without `decl!` have no location, so they are described by the
+                // scope which declared them instead.
+                let mut printed_unknown = false;
+
+                for call_site in &caller.call_sites {
+                    if call_site == UNKNOWN_LOCATION {
+                        if !printed_unknown {
+                            writeln!(
+                                f,
+                                "{}- An invocation declared by the scope {} at an unknown location",
+                                Indent(indent + INDENT_SIZE),
+                                caller_scope.name,
+                            )?;
+                        }
+                        printed_unknown = true;
+                    } else {
+                        writeln!(f, "{}- {}", Indent(indent + INDENT_SIZE), call_site)?;
+                    }
+                }
+
+                if caller.node == 0 {
+                    writeln!(
+                        f,
+                        "{}  This is the scope being explained, which may be re-entered through a cycle.",
+                        Indent(indent),
+                    )?;
+                } else {
+                    writeln!(
+                        f,
+                        "{}  Responsibility for this scope is traced back at [{}].",
+                        Indent(indent),
+                        caller.node,
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,445 @@
+//! Comparison of the scope graphs of two builds.
+//!
+//! Scopes are matched by name rather than by their disambiguator, which changes whenever their
+//! definition moves within its file. Scopes which share their name with another scope of the same
+//! build, such as the scopes of `scope!` blocks, are told apart by their location instead.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+
+use crate::{
+    diagnostic::{ScopeKeys, ScopeRef},
+    validator::{Mutability, ScopeSnapshot},
+};
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GraphDiff {
+    pub added_scopes: Vec<String>,
+    pub removed_scopes: Vec<String>,
+    pub added_calls: Vec<CallChange>,
+    pub removed_calls: Vec<CallChange>,
+    pub added_borrows: Vec<AccessChange>,
+    pub removed_borrows: Vec<AccessChange>,
+    pub added_transient_borrows: Vec<AccessChange>,
+    pub removed_transient_borrows: Vec<AccessChange>,
+    pub added_grants: Vec<AccessChange>,
+    pub removed_grants: Vec<AccessChange>,
+    /// Changes to the way in which components could possibly be borrowed when entering scopes
+    /// present in both builds.
+    pub held_changes: Vec<HeldChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CallChange {
+    pub caller: String,
+    pub callee: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccessChange {
+    pub scope: String,
+    pub component: String,
+    pub mutability: Mutability,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HeldChange {
+    pub scope: String,
+    pub component: String,
+    /// The way in which the component could be held before the change, if at all.
+    pub old: Option<Mutability>,
+    /// The way in which the component could be held after the change, if at all.
+    pub new: Option<Mutability>,
+}
+
+impl GraphDiff {
+    pub fn between(old: &[ScopeSnapshot], new: &[ScopeSnapshot]) -> Self {
+        let old = KeyedScopes::new(old);
+        let new = KeyedScopes::new(new);
+        let empty = ScopeSnapshot::empty();
+
+        let mut diff = Self::default();
+
+        for name in old
+            .scopes
+            .keys()
+            .chain(new.scopes.keys())
+            .collect::<BTreeSet<_>>()
+        {
+            let (old_scope, new_scope) = match (old.scopes.get(name), new.scopes.get(name)) {
+                (Some(old), Some(new)) => (*old, *new),
+                (Some(old), None) => {
+                    diff.removed_scopes.push(name.to_string());
+                    (*old, &empty)
+                }
+                (None, Some(new)) => {
+                    diff.added_scopes.push(name.to_string());
+                    (&empty, *new)
+                }
+                (None, None) => unreachable!(),
+            };
+
+            let old_calls = old.calls(old_scope);
+            let new_calls = new.calls(new_scope);
+
+            for callee in new_calls.difference(&old_calls) {
+                diff.added_calls.push(CallChange {
+                    caller: name.to_string(),
+                    callee: callee.to_string(),
+                });
+            }
+
+            for callee in old_calls.difference(&new_calls) {
+                diff.removed_calls.push(CallChange {
+                    caller: name.to_string(),
+                    callee: callee.to_string(),
+                });
+            }
+
+            diff_accesses(
+                name,
+                &old_scope.borrows,
+                &new_scope.borrows,
+                &mut diff.added_borrows,
+                &mut diff.removed_borrows,
+            );
+
+            diff_accesses(
+                name,
+                &old_scope.transient_borrows,
+                &new_scope.transient_borrows,
+                &mut diff.added_transient_borrows,
+                &mut diff.removed_transient_borrows,
+            );
+
+            diff_accesses(
+                name,
+                &old_scope.grants,
+                &new_scope.grants,
+                &mut diff.added_grants,
+                &mut diff.removed_grants,
+            );
+
+            // Added and removed scopes are fully described by the changes above.
+            if !old.scopes.contains_key(name) || !new.scopes.contains_key(name) {
+                continue;
+            }
+
+            let components = old_scope
+                .potentially_borrowed
+                .keys()
+                .chain(new_scope.potentially_borrowed.keys())
+                .collect::<BTreeSet<_>>();
+
+            for component in components {
+                let old_held = old_scope.potentially_borrowed.get(component).copied();
+                let new_held = new_scope.potentially_borrowed.get(component).copied();
+
+                if old_held != new_held {
+                    diff.held_changes.push(HeldChange {
+                        scope: name.to_string(),
+                        component: component.clone(),
+                        old: old_held,
+                        new: new_held,
+                    });
+                }
+            }
+        }
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added_scopes.is_empty()
+            && self.removed_scopes.is_empty()
+            && self.added_calls.is_empty()
+            && self.removed_calls.is_empty()
+            && self.added_borrows.is_empty()
+            && self.removed_borrows.is_empty()
+            && self.added_transient_borrows.is_empty()
+            && self.removed_transient_borrows.is_empty()
+            && self.added_grants.is_empty()
+            && self.removed_grants.is_empty()
+            && self.held_changes.is_empty()
+    }
+}
+
+/// The scopes of a build keyed by the names under which they are matched with the other build.
+struct KeyedScopes<'s> {
+    scopes: BTreeMap<String, &'s ScopeSnapshot>,
+    keys: FxHashMap<&'s str, String>,
+}
+
+impl<'s> KeyedScopes<'s> {
+    fn new(snapshots: &'s [ScopeSnapshot]) -> Self {
+        let scope_keys = ScopeKeys::new(snapshots.iter().map(|snapshot| &snapshot.scope));
+
+        let mut scopes = BTreeMap::new();
+        let mut keys = FxHashMap::default();
+
+        for snapshot in snapshots {
+            let scope = &snapshot.scope;
+            let key = scope_keys.key(scope);
+
+            keys.insert(scope.id.as_str(), key.clone());
+            scopes.insert(key, snapshot);
+        }
+
+        Self { scopes, keys }
+    }
+
+    /// The keys of the scopes called by `scope`.
+    fn calls<'a>(&'a self, scope: &'a ScopeSnapshot) -> BTreeSet<&'a str> {
+        scope
+            .calls
+            .iter()
+            .map(|callee| {
+                self.keys
+                    .get(callee.as_str())
+                    .map_or(callee.as_str(), String::as_str)
+            })
+            .collect()
+    }
+}
+
+/// Records the accesses which differ between two builds of a scope. An access whose mutability
+/// changed is reported as removed in one form and added in the other.
+fn diff_accesses(
+    scope: &str,
+    old: &BTreeMap<String, Mutability>,
+    new: &BTreeMap<String, Mutability>,
+    added: &mut Vec<AccessChange>,
+    removed: &mut Vec<AccessChange>,
+) {
+    let change = |(component, mutability): (&String, &Mutability)| AccessChange {
+        scope: scope.to_string(),
+        component: component.clone(),
+        mutability: *mutability,
+    };
+
+    added.extend(
+        new.iter()
+            .filter(|(component, mutability)| old.get(*component) != Some(*mutability))
+            .map(change),
+    );
+
+    removed.extend(
+        old.iter()
+            .filter(|(component, mutability)| new.get(*component) != Some(*mutability))
+            .map(change),
+    );
+}
+
+impl ScopeSnapshot {
+    fn empty() -> Self {
+        Self {
+            scope: ScopeRef {
+                id: String::new(),
+                name: String::new(),
+                defined_at: String::new(),
+            },
+            borrows: BTreeMap::new(),
+            transient_borrows: BTreeMap::new(),
+            grants: BTreeMap::new(),
+            calls: BTreeSet::new(),
+            potentially_borrowed: BTreeMap::new(),
+        }
+    }
+}
+
+// === Human Rendering === //
+
+fn held_description(held: Option<Mutability>) -> &'static str {
+    match held {
+        None => "not held",
+        Some(mutability) => mutability.adjective(),
+    }
+}
+
+impl fmt::Display for GraphDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "The scope graphs are identical.");
+        }
+
+        let section = |f: &mut fmt::Formatter<'_>, title: &str, lines: Vec<String>| {
+            if lines.is_empty() {
+                return Ok(());
+            }
+
+            writeln!(f, "{title}:")?;
+            for line in lines {
+                writeln!(f, "    {line}")?;
+            }
+            writeln!(f)
+        };
+
+        section(
+            f,
+            "Scopes",
+            (self.added_scopes.iter().map(|scope| format!("+ {scope}")))
+                .chain(self.removed_scopes.iter().map(|scope| format!("- {scope}")))
+                .collect(),
+        )?;
+
+        section(
+            f,
+            "Calls",
+            (self.added_calls.iter().map(|call| ('+', call)))
+                .chain(self.removed_calls.iter().map(|call| ('-', call)))
+                .map(|(sign, call)| format!("{sign} {} -> {}", call.caller, call.callee))
+                .collect(),
+        )?;
+
+        for (title, added, removed) in [
+            ("Borrows", &self.added_borrows, &self.removed_borrows),
+            (
+                "Transient borrows",
+                &self.added_transient_borrows,
+                &self.removed_transient_borrows,
+            ),
+            ("Grants", &self.added_grants, &self.removed_grants),
+        ] {
+            section(
+                f,
+                title,
+                (added.iter().map(|access| ('+', access)))
+                    .chain(removed.iter().map(|access| ('-', access)))
+                    .map(|(sign, access)| {
+                        format!(
+                            "{sign} {} {} {}",
+                            access.scope,
+                            access.mutability.keyword(),
+                            access.component,
+                        )
+                    })
+                    .collect(),
+            )?;
+        }
+
+        section(
+            f,
+            "Potentially borrowed on entry",
+            self.held_changes
+                .iter()
+                .map(|change| {
+                    format!(
+                        "~ {} {}: {} -> {}",
+                        change.scope,
+                        change.component,
+                        held_description(change.old),
+                        held_description(change.new),
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use Mutability::{Immutable as Ref, Mutable as Mut};
+
+    fn snapshot(
+        id: &str,
+        name: &str,
+        defined_at: &str,
+        calls: &[&str],
+        borrows: &[(&str, Mutability)],
+    ) -> ScopeSnapshot {
+        ScopeSnapshot {
+            scope: ScopeRef {
+                id: id.to_string(),
+                name: name.to_string(),
+                defined_at: defined_at.to_string(),
+            },
+            borrows: borrows
+                .iter()
+                .map(|&(component, mutability)| (component.to_string(), mutability))
+                .collect(),
+            calls: calls.iter().map(|callee| callee.to_string()).collect(),
+            ..ScopeSnapshot::empty()
+        }
+    }
+
+    #[test]
+    fn moved_scopes_are_matched_by_name() {
+        let old = [snapshot(
+            "a1",
+            "Update",
+            "src/a.rs:1:1",
+            &[],
+            &[("u32", Ref)],
+        )];
+        let new = [snapshot(
+            "a2",
+            "Update",
+            "src/a.rs:9:1",
+            &[],
+            &[("u32", Ref)],
+        )];
+
+        assert!(GraphDiff::between(&old, &new).is_empty());
+    }
+
+    #[test]
+    fn same_named_scopes_are_kept_apart() {
+        let old = [
+            snapshot("root", "Root", "src/a.rs:1:1", &["b1", "b2"], &[]),
+            snapshot("b1", "Block", "src/a.rs:2:1", &[], &[("u32", Ref)]),
+            snapshot("b2", "Block", "src/a.rs:3:1", &[], &[("u64", Mut)]),
+        ];
+        let new = [
+            snapshot("root", "Root", "src/a.rs:1:1", &["b2"], &[]),
+            snapshot("b1", "Block", "src/a.rs:2:1", &[], &[("u32", Ref)]),
+            snapshot("b2", "Block", "src/a.rs:3:1", &[], &[("u64", Ref)]),
+        ];
+
+        let diff = GraphDiff::between(&old, &new);
+        assert!(diff.added_scopes.is_empty() && diff.removed_scopes.is_empty());
+
+        let calls = |calls: &[CallChange]| {
+            calls
+                .iter()
+                .map(|call| (call.caller.clone(), call.callee.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert!(diff.added_calls.is_empty());
+        assert_eq!(
+            calls(&diff.removed_calls),
+            [(
+                "Root".to_string(),
+                "Block (defined at src/a.rs:2:1)".to_string()
+            )],
+        );
+
+        let accesses = |accesses: &[AccessChange]| {
+            accesses
+                .iter()
+                .map(|access| {
+                    (
+                        access.scope.clone(),
+                        access.component.clone(),
+                        access.mutability,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let block = "Block (defined at src/a.rs:3:1)".to_string();
+        assert_eq!(
+            accesses(&diff.added_borrows),
+            [(block.clone(), "u64".to_string(), Ref)],
+        );
+        assert_eq!(
+            accesses(&diff.removed_borrows),
+            [(block, "u64".to_string(), Mut)],
+        );
+    }
+}
@@ -0,0 +1,130 @@
+//! Resolution of code addresses to source locations using a binary's DWARF debug info.
+
+use std::path::Path;
+
+use anyhow::Context;
+use object::{Object, ObjectSection};
+
+use crate::elf::is_saddle_internal;
+
+type Reader<'data> = gimli::EndianSlice<'data, gimli::RunTimeEndian>;
+
+pub struct LineTable<'data> {
+    context: addr2line::Context<Reader<'data>>,
+}
+
+impl<'data> LineTable<'data> {
+    pub fn new(file: &object::File<'data>) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            file.section_by_name(".debug_info").is_some(),
+            "The binary has no debug info. Build it with `debug = true` to use --debug-info."
+        );
+
+        let endian = if file.is_little_endian() {
+            gimli::RunTimeEndian::Little
+        } else {
+            gimli::RunTimeEndian::Big
+        };
+
+        // Compressed sections are not supported and are treated as missing.
+        let dwarf = gimli::Dwarf::load(|id| {
+            let data = file
+                .section_by_name(id.name())
+                .filter(|section| {
+                    section
+                        .compressed_file_range()
+                        .is_ok_and(|range| range.format == object::CompressionFormat::None)
+                })
+                .map_or(Ok(&[][..]), |section| section.data())?;
+
+            anyhow::Ok(gimli::EndianSlice::new(data, endian))
+        })?;
+
+        let context = addr2line::Context::from_dwarf(dwarf)
+            .context("failed to parse the binary's debug info")?;
+
+        Ok(Self { context })
+    }
+
+    /// Determines the `file:line:column` of the instruction at `address`. If the instruction
+    /// belongs to code inlined from saddle, this is the location at which user code calls into
+    /// saddle.
+    pub fn locate(&self, address: u64) -> Option<String> {
+        let comp_dir = self
+            .context
+            .find_dwarf_and_unit(address)
+            .skip_all_loads()
+            .and_then(|unit| unit.comp_dir)
+            .map(|dir| dir.to_string_lossy());
+
+        let mut frames = self.context.find_frames(address).skip_all_loads().ok()?;
+        let mut fallback = None;
+
+        while let Ok(Some(frame)) = frames.next() {
+            let Some(location) = frame
+                .location
+                .and_then(|location| format_location(location, comp_dir.as_deref()))
+            else {
+                continue;
+            };
+
+            let internal = frame
+                .function
+                .as_ref()
+                .and_then(|function| function.raw_name().ok())
+                .is_some_and(|name| {
+                    is_saddle_internal(&format!("{:#}", rustc_demangle::demangle(&name)))
+                });
+
+            if !internal {
+                return Some(location);
+            }
+
+            fallback = Some(location);
+        }
+
+        fallback
+    }
+}
+
+/// Debug info records absolute paths while `file!()`, and therefore the locations recorded by
+/// `decl!`, is relative to the directory the compiler was run from. Cargo runs it from the
+/// workspace root, which is recorded as the compilation directory of each unit, so paths are made
+/// relative to it for the two to match.
+fn relative_to_comp_dir<'p>(file: &'p Path, comp_dir: Option<&str>) -> &'p Path {
+    comp_dir
+        .and_then(|dir| file.strip_prefix(dir).ok())
+        .unwrap_or(file)
+}
+
+fn format_location(location: addr2line::Location, comp_dir: Option<&str>) -> Option<String> {
+    let file = relative_to_comp_dir(Path::new(location.file?), comp_dir);
+
+    Some(format!(
+        "{}:{}:{}",
+        file.display(),
+        location.line?,
+        location.column.unwrap_or(1),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_are_relative_to_comp_dir() {
+        let file = Path::new("/home/me/project/src/main.rs");
+
+        assert_eq!(
+            relative_to_comp_dir(file, Some("/home/me/project")),
+            Path::new("src/main.rs"),
+        );
+        assert_eq!(
+            relative_to_comp_dir(file, Some("/home/me/project/")),
+            Path::new("src/main.rs"),
+        );
+        assert_eq!(relative_to_comp_dir(file, Some("/elsewhere")), file);
+        assert_eq!(relative_to_comp_dir(file, None), file);
+    }
+}
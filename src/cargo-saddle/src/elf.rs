@@ -0,0 +1,453 @@
+//! An ELF-aware mode which maps each marker back to the functions whose code references it.
+//!
+//! Markers are `type_name` strings in `.rodata`, so we find the instructions which load their
+//! address and attribute them to the function symbol containing each instruction. These references
+//! come from two sources:
+//!
+//! 1. Relocations against code sections, which are only present in object files and binaries linked
+//!    with `--emit-relocs`.
+//! 2. A heuristic scan of x86-64 code for RIP-relative `lea` instructions, which is how fully-linked
+//!    binaries load the address of a string.
+//!
+//! In unoptimized builds, the reference usually lives in a monomorphization of `type_name` or of one
+//! of saddle's `decl_*` helpers rather than in user code, so we walk up through the direct callers of
+//! such functions until we reach a function outside of saddle.
+
+use anyhow::Context;
+use object::{
+    Architecture, Object, ObjectSection, ObjectSymbol, RelocationKind, RelocationTarget,
+    SectionKind, SymbolKind,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{decoder::DecodedBinary, dwarf::LineTable};
+
+/// The maximum number of saddle-internal functions we walk through when looking for the function
+/// responsible for a marker.
+const MAX_WRAPPER_DEPTH: usize = 8;
+
+/// The path preceding the name of every marker in its `type_name`.
+const MARKER_PATH: &[u8] = b"saddle::";
+
+struct Function {
+    address: u64,
+    size: u64,
+    name: String,
+}
+
+struct CodeIndex {
+    /// Every function symbol, sorted by address.
+    functions: Vec<Function>,
+    /// Maps addresses referenced by code to the addresses of the instructions referencing them.
+    data_refs: FxHashMap<u64, Vec<u64>>,
+    /// Maps the entry address of each function to the addresses of the instructions calling it.
+    callers: FxHashMap<u64, Vec<u64>>,
+    /// The `(file_offset, size, address)` of every section loaded into memory.
+    loaded_ranges: Vec<(u64, u64, u64)>,
+}
+
+impl CodeIndex {
+    fn new(file: &object::File) -> anyhow::Result<Self> {
+        // Collect function symbols.
+        let mut functions = file
+            .symbols()
+            .filter(|sym| sym.kind() == SymbolKind::Text && sym.size() > 0)
+            .filter_map(|sym| {
+                Some(Function {
+                    address: sym.address(),
+                    size: sym.size(),
+                    name: format!("{:#}", rustc_demangle::demangle(sym.name().ok()?)),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        anyhow::ensure!(
+            !functions.is_empty(),
+            "The binary has no function symbols. ELF mode cannot be used on stripped binaries."
+        );
+
+        functions.sort_by_key(|func| func.address);
+        functions.dedup_by_key(|func| func.address);
+
+        let function_starts = functions
+            .iter()
+            .map(|func| func.address)
+            .collect::<FxHashSet<_>>();
+
+        let mut index = Self {
+            functions,
+            data_refs: FxHashMap::default(),
+            callers: FxHashMap::default(),
+            loaded_ranges: Vec::new(),
+        };
+
+        for section in file.sections() {
+            if let Some((offset, size)) = section.file_range() {
+                if section.address() != 0 {
+                    index.loaded_ranges.push((offset, size, section.address()));
+                }
+            }
+
+            if section.kind() != SectionKind::Text {
+                continue;
+            }
+
+            // Record references made through relocations.
+            for (offset, reloc) in section.relocations() {
+                let target = match reloc.target() {
+                    RelocationTarget::Symbol(idx) => file.symbol_by_index(idx)?.address(),
+                    RelocationTarget::Section(idx) => file.section_by_index(idx)?.address(),
+                    _ => continue,
+                };
+
+                // PC-relative relocations are biased by the size of the displacement.
+                let bias = match reloc.kind() {
+                    RelocationKind::Relative | RelocationKind::PltRelative => 4,
+                    _ => 0,
+                };
+
+                let target = target
+                    .wrapping_add_signed(reloc.addend())
+                    .wrapping_add(bias);
+                index.record(&function_starts, section.address() + offset, target);
+            }
+
+            // Record references made through RIP-relative addressing.
+            if file.architecture() == Architecture::X86_64 {
+                let data = section
+                    .data()
+                    .context("failed to read code section of binary")?;
+
+                index.scan_x86_64(&function_starts, section.address(), data);
+            }
+        }
+
+        Ok(index)
+    }
+
+    fn record(&mut self, function_starts: &FxHashSet<u64>, source: u64, target: u64) {
+        if function_starts.contains(&target) {
+            self.callers.entry(target).or_default().push(source);
+        } else {
+            self.data_refs.entry(target).or_default().push(source);
+        }
+    }
+
+    fn scan_x86_64(&mut self, function_starts: &FxHashSet<u64>, base: u64, code: &[u8]) {
+        let disp_at = |i: usize| i32::from_le_bytes(code[i..i + 4].try_into().unwrap()) as i64;
+
+        for i in 0..code.len() {
+            // `lea r64, [rip + disp32]`
+            if i + 7 <= code.len()
+                && matches!(code[i], 0x48 | 0x4c)
+                && code[i + 1] == 0x8d
+                && code[i + 2] & 0xc7 == 0x05
+            {
+                let target = (base + i as u64 + 7).wrapping_add_signed(disp_at(i + 3));
+                self.data_refs
+                    .entry(target)
+                    .or_default()
+                    .push(base + i as u64);
+            }
+
+            // `call rel32` and `jmp rel32`. These are only trusted if they land exactly on the
+            // start of a function since this scan does not decode instruction boundaries.
+            if i + 5 <= code.len() && matches!(code[i], 0xe8 | 0xe9) {
+                let target = (base + i as u64 + 5).wrapping_add_signed(disp_at(i + 1));
+                if function_starts.contains(&target) {
+                    self.callers
+                        .entry(target)
+                        .or_default()
+                        .push(base + i as u64);
+                }
+            }
+        }
+    }
+
+    fn address_of_offset(&self, offset: u64) -> Option<u64> {
+        self.loaded_ranges
+            .iter()
+            .find(|&&(start, size, _)| (start..start + size).contains(&offset))
+            .map(|&(start, _, address)| address + (offset - start))
+    }
+
+    fn function_containing(&self, address: u64) -> Option<&Function> {
+        let idx = self
+            .functions
+            .partition_point(|func| func.address <= address)
+            .checked_sub(1)?;
+
+        let func = &self.functions[idx];
+        (address < func.address + func.size).then_some(func)
+    }
+
+    /// Lists the instructions referencing the marker at `offset` in the file, or `None` if the
+    /// marker is not loaded into memory.
+    fn marker_references(&self, data: &[u8], offset: usize) -> Option<Vec<u64>> {
+        let address = self.address_of_offset(offset as u64)?;
+
+        // The marker is preceded by the path of saddle, which defines it at its root. That's where
+        // the string referenced by code actually begins. Strings are laid out back to back, so we
+        // don't look any further back to avoid picking up references to the preceding string.
+        let start = if data[..offset].ends_with(MARKER_PATH) {
+            address - MARKER_PATH.len() as u64
+        } else {
+            address
+        };
+
+        Some(self.data_refs.get(&start).cloned().unwrap_or_default())
+    }
+
+    /// Collects the entry points named by `entry_point_of` from which the instruction at `source`
+    /// may be reached through direct calls.
+    fn reaching_entry_points(
+        &self,
+        source: u64,
+        entry_point_of: &impl Fn(&str) -> Option<String>,
+        out: &mut FxHashSet<String>,
+    ) {
+        let Some(func) = self.function_containing(source) else {
+            return;
+        };
+
+        let mut visited = FxHashSet::from_iter([func.address]);
+        let mut stack = vec![func];
+
+        while let Some(func) = stack.pop() {
+            if let Some(entry_point) = entry_point_of(&func.name) {
+                out.insert(entry_point);
+                continue;
+            }
+
+            for &site in self.callers.get(&func.address).into_iter().flatten() {
+                if let Some(caller) = self.function_containing(site) {
+                    if visited.insert(caller.address) {
+                        stack.push(caller);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Determines the functions responsible for the references made by the instruction at
+    /// `source`, looking through saddle's own wrappers. Each function is reported alongside the
+    /// address of the instruction within it which leads to the reference.
+    fn responsible_sites<'s>(&'s self, source: u64, out: &mut FxHashSet<(&'s str, u64)>) {
+        let Some(func) = self.function_containing(source) else {
+            return;
+        };
+
+        let mut visited = FxHashSet::from_iter([func.address]);
+        let mut frontier = vec![(func, source)];
+
+        for _ in 0..MAX_WRAPPER_DEPTH {
+            let mut next = Vec::new();
+
+            for (func, site) in frontier {
+                if !is_saddle_internal(&func.name) {
+                    out.insert((&func.name, site));
+                    continue;
+                }
+
+                let callers = self
+                    .callers
+                    .get(&func.address)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|&site| Some((self.function_containing(site)?, site)))
+                    .filter(|(caller, _)| visited.insert(caller.address))
+                    .collect::<Vec<_>>();
+
+                // If nothing calls this wrapper directly, it is the best we can do.
+                if callers.is_empty() {
+                    out.insert((&func.name, site));
+                }
+
+                next.extend(callers);
+            }
+
+            if next.is_empty() {
+                return;
+            }
+
+            frontier = next;
+        }
+
+        out.extend(
+            frontier
+                .into_iter()
+                .map(|(func, site)| (func.name.as_str(), site)),
+        );
+    }
+}
+
+pub(crate) fn is_saddle_internal(name: &str) -> bool {
+    name.starts_with("core::any::type_name")
+        || name.starts_with("saddle::")
+        || name.starts_with("<saddle::")
+        || name.contains(" as saddle::Scope>::")
+}
+
+fn parse_elf(data: &[u8]) -> anyhow::Result<object::File<'_>> {
+    let file = object::File::parse(data).context("failed to parse binary as an object file")?;
+    anyhow::ensure!(
+        file.format() == object::BinaryFormat::Elf,
+        "ELF mode can only be used on ELF binaries."
+    );
+
+    Ok(file)
+}
+
+/// How the sites referencing a marker are reported.
+#[derive(Debug, Copy, Clone)]
+pub enum Attribution {
+    /// By the demangled name of the function containing them.
+    Function,
+    /// By the `file:line:column` recorded for them in the binary's DWARF debug info.
+    DebugInfo,
+}
+
+/// Fills in [`DecoderEntry::referenced_by`](crate::decoder::DecoderEntry::referenced_by) for
+/// every entry of an ELF binary. Entries found outside of the sections loaded into memory (e.g.
+/// type names in debug info) are not referenced by any code and are dropped.
+pub fn resolve_references(
+    data: &[u8],
+    binary: &mut DecodedBinary,
+    attribution: Attribution,
+) -> anyhow::Result<()> {
+    let file = parse_elf(data)?;
+    let index = CodeIndex::new(&file)?;
+    let lines = match attribution {
+        Attribution::Function => None,
+        Attribution::DebugInfo => Some(LineTable::new(&file)?),
+    };
+
+    binary.entries.retain_mut(|entry| {
+        let Some(sources) = index.marker_references(data, entry.offset) else {
+            return false;
+        };
+
+        let mut sites = FxHashSet::default();
+
+        for source in sources {
+            index.responsible_sites(source, &mut sites);
+        }
+
+        entry.referenced_by = sites
+            .into_iter()
+            .filter_map(|(function, site)| match &lines {
+                Some(lines) => lines.locate(site),
+                None => Some(function.to_string()),
+            })
+            .collect();
+
+        entry.referenced_by.sort();
+        entry.referenced_by.dedup();
+        true
+    });
+
+    Ok(())
+}
+
+/// Determines, for every entry of an ELF binary, the entry points from which the code referencing
+/// it may be reached through direct calls. `entry_point_of` maps the demangled name of a function
+/// to the name of the entry point it implements, if any. Unlike [`resolve_references`], this walks
+/// past user code since entry points such as tests usually call into helpers.
+pub fn find_entry_points(
+    data: &[u8],
+    binary: &DecodedBinary,
+    entry_point_of: impl Fn(&str) -> Option<String>,
+) -> anyhow::Result<Vec<Vec<String>>> {
+    let file = parse_elf(data)?;
+    let index = CodeIndex::new(&file)?;
+
+    Ok(binary
+        .entries
+        .iter()
+        .map(|entry| {
+            let mut entry_points = FxHashSet::default();
+
+            for source in index
+                .marker_references(data, entry.offset)
+                .into_iter()
+                .flatten()
+            {
+                index.reaching_entry_points(source, &entry_point_of, &mut entry_points);
+            }
+
+            let mut entry_points = entry_points.into_iter().collect::<Vec<_>>();
+            entry_points.sort();
+            entry_points
+        })
+        .collect())
+}
+
+// The tests analyze their own executable, which only the x86-64 heuristics can attribute without
+// relocations.
+#[cfg(all(test, target_os = "linux", target_arch = "x86_64"))]
+mod tests {
+    use super::*;
+    use crate::decoder::decode_binary;
+    use saddle::Scope;
+
+    saddle::scope!(ElfTestScope);
+
+    #[inline(never)]
+    fn declares_a_borrow() {
+        saddle::decl!(ElfTestScope::new() => mut u32);
+    }
+
+    /// Decodes the marker emitted by [`declares_a_borrow`] in this executable. The executable also
+    /// contains marker-like literals from the decoder tests, so we only decode this one. Its prefix
+    /// is built at runtime so that it isn't itself a literal in the executable.
+    fn decode_own_marker(data: &[u8]) -> DecodedBinary {
+        let prefix = format!(
+            "SaddleInternalV2DeclForDepMut<{}, u32, ",
+            std::any::type_name::<<ElfTestScope as Scope>::_InternalDisamb>(),
+        );
+
+        let start = memchr::memmem::find(data, prefix.as_bytes())
+            .expect("the marker should be in the executable");
+        let end = memchr::memmem::find(&data[start..], b">>").unwrap() + start + 2;
+
+        let mut binary = decode_binary(&data[start..end]).unwrap();
+        assert_eq!(binary.entries.len(), 1);
+        binary.entries[0].offset += start;
+        binary
+    }
+
+    #[test]
+    fn markers_are_attributed_to_their_functions() {
+        declares_a_borrow();
+
+        let data = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+        let mut binary = decode_own_marker(&data);
+
+        resolve_references(&data, &mut binary, Attribution::Function).unwrap();
+        assert_eq!(
+            binary.entries[0].referenced_by,
+            ["cargo_saddle::elf::tests::declares_a_borrow"],
+        );
+    }
+
+    #[test]
+    fn entry_points_reaching_markers_are_found() {
+        declares_a_borrow();
+
+        let data = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+        let binary = decode_own_marker(&data);
+
+        let reached_by = find_entry_points(&data, &binary, |function| {
+            function
+                .strip_prefix("cargo_saddle::")
+                .filter(|&test| test == "elf::tests::entry_points_reaching_markers_are_found")
+                .map(str::to_string)
+        })
+        .unwrap();
+
+        assert_eq!(
+            reached_by,
+            [["elf::tests::entry_points_reaching_markers_are_found"]],
+        );
+    }
+}
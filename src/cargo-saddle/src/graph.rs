@@ -0,0 +1,286 @@
+//! Renderers for the scope call graph exported by [`Validator::export_graph`](crate::validator::Validator::export_graph).
+
+use std::fmt::Write;
+
+use crate::validator::{Mutability, ScopeGraph, ScopeGraphEdge, ScopeGraphNode};
+
+/// Lists the scope's name followed by its borrow and grant sets, one per line.
+fn node_label_lines(node: &ScopeGraphNode) -> Vec<String> {
+    let mut lines = vec![node.scope.name.clone()];
+
+    for (component, mutability) in &node.borrows {
+        lines.push(format!("{} {component}", mutability.keyword()));
+    }
+
+    for (component, mutability) in &node.transient_borrows {
+        lines.push(format!("transient {} {component}", mutability.keyword()));
+    }
+
+    for (component, mutability) in &node.grants {
+        lines.push(format!("grant {} {component}", mutability.keyword()));
+    }
+
+    lines
+}
+
+/// Labels an edge with its call site and, if declared, the components held during the call.
+fn edge_label(edge: &ScopeGraphEdge) -> String {
+    match edge.held.as_deref() {
+        Some([]) => format!("{} (holding nothing)", edge.call_site),
+        Some(held) => format!("{} (holding {})", edge.call_site, held.join(", ")),
+        None => edge.call_site.clone(),
+    }
+}
+
+// === DOT === //
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+pub fn to_dot(graph: &ScopeGraph) -> String {
+    let mut f = String::new();
+    f.push_str("digraph saddle {\n");
+    f.push_str("    node [shape=box];\n");
+
+    for (i, node) in graph.nodes.iter().enumerate() {
+        let label = node_label_lines(node)
+            .iter()
+            .map(|line| escape_dot(line))
+            .collect::<Vec<_>>()
+            .join("\\n");
+
+        writeln!(f, "    n{i} [label=\"{label}\"];").unwrap();
+    }
+
+    for edge in &graph.edges {
+        writeln!(
+            f,
+            "    n{} -> n{} [label=\"{}\"];",
+            edge.from,
+            edge.to,
+            escape_dot(&edge_label(edge)),
+        )
+        .unwrap();
+    }
+
+    f.push_str("}\n");
+    f
+}
+
+// === Mermaid === //
+
+fn escape_mermaid(text: &str) -> String {
+    text.replace('&', "#amp;")
+        .replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+pub fn to_mermaid(graph: &ScopeGraph) -> String {
+    let mut f = String::new();
+    f.push_str("flowchart TD\n");
+
+    for (i, node) in graph.nodes.iter().enumerate() {
+        let label = node_label_lines(node)
+            .iter()
+            .map(|line| escape_mermaid(line))
+            .collect::<Vec<_>>()
+            .join("<br/>");
+
+        writeln!(f, "    n{i}[\"{label}\"]").unwrap();
+    }
+
+    for edge in &graph.edges {
+        writeln!(
+            f,
+            "    n{} -->|\"{}\"| n{}",
+            edge.from,
+            escape_mermaid(&edge_label(edge)),
+            edge.to,
+        )
+        .unwrap();
+    }
+
+    f
+}
+
+// === GraphML === //
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn to_graphml(graph: &ScopeGraph) -> String {
+    let mut f = String::new();
+    f.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    f.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+    f.push_str("  <key id=\"name\" for=\"node\" attr.name=\"name\" attr.type=\"string\"/>\n");
+    f.push_str(
+        "  <key id=\"defined_at\" for=\"node\" attr.name=\"defined_at\" attr.type=\"string\"/>\n",
+    );
+    f.push_str("  <key id=\"borrows\" for=\"node\" attr.name=\"borrows\" attr.type=\"string\"/>\n");
+    f.push_str(
+        "  <key id=\"transient_borrows\" for=\"node\" attr.name=\"transient_borrows\" attr.type=\"string\"/>\n",
+    );
+    f.push_str("  <key id=\"grants\" for=\"node\" attr.name=\"grants\" attr.type=\"string\"/>\n");
+    f.push_str(
+        "  <key id=\"call_site\" for=\"edge\" attr.name=\"call_site\" attr.type=\"string\"/>\n",
+    );
+    f.push_str("  <key id=\"held\" for=\"edge\" attr.name=\"held\" attr.type=\"string\"/>\n");
+    f.push_str("  <graph id=\"saddle\" edgedefault=\"directed\">\n");
+
+    let list = |set: &[(String, Mutability)]| {
+        set.iter()
+            .map(|(component, mutability)| {
+                escape_xml(&format!("{} {component}", mutability.keyword()))
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    };
+
+    for (i, node) in graph.nodes.iter().enumerate() {
+        writeln!(f, "    <node id=\"n{i}\">").unwrap();
+        writeln!(
+            f,
+            "      <data key=\"name\">{}</data>",
+            escape_xml(&node.scope.name),
+        )
+        .unwrap();
+        writeln!(
+            f,
+            "      <data key=\"defined_at\">{}</data>",
+            escape_xml(&node.scope.defined_at),
+        )
+        .unwrap();
+        writeln!(
+            f,
+            "      <data key=\"borrows\">{}</data>",
+            list(&node.borrows)
+        )
+        .unwrap();
+        writeln!(
+            f,
+            "      <data key=\"transient_borrows\">{}</data>",
+            list(&node.transient_borrows)
+        )
+        .unwrap();
+        writeln!(
+            f,
+            "      <data key=\"grants\">{}</data>",
+            list(&node.grants)
+        )
+        .unwrap();
+        f.push_str("    </node>\n");
+    }
+
+    for (i, edge) in graph.edges.iter().enumerate() {
+        writeln!(
+            f,
+            "    <edge id=\"e{i}\" source=\"n{}\" target=\"n{}\">",
+            edge.from, edge.to,
+        )
+        .unwrap();
+        writeln!(
+            f,
+            "      <data key=\"call_site\">{}</data>",
+            escape_xml(&edge.call_site),
+        )
+        .unwrap();
+        if let Some(held) = &edge.held {
+            writeln!(
+                f,
+                "      <data key=\"held\">{}</data>",
+                escape_xml(&held.join("; ")),
+            )
+            .unwrap();
+        }
+        f.push_str("    </edge>\n");
+    }
+
+    f.push_str("  </graph>\n");
+    f.push_str("</graphml>\n");
+    f
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostic::ScopeRef;
+
+    /// Two scopes, one of which calls the other while holding a generic component.
+    fn graph() -> ScopeGraph {
+        let node = |name: &str, borrows: Vec<(String, Mutability)>| ScopeGraphNode {
+            scope: ScopeRef {
+                id: format!("app::{name}"),
+                name: name.to_string(),
+                defined_at: format!("src/{name}.rs:1:1"),
+            },
+            borrows,
+            transient_borrows: Vec::new(),
+            grants: Vec::new(),
+        };
+
+        ScopeGraph {
+            nodes: vec![
+                node("Inner", Vec::new()),
+                node("Outer", vec![("Vec<u32>".to_string(), Mutability::Mutable)]),
+            ],
+            edges: vec![ScopeGraphEdge {
+                from: 1,
+                to: 0,
+                call_site: "src/Outer.rs:4:9".to_string(),
+                held: Some(vec!["Vec<u32>".to_string()]),
+            }],
+        }
+    }
+
+    #[test]
+    fn dot_is_rendered() {
+        let dot = to_dot(&graph());
+
+        assert!(
+            dot.contains("n1 [label=\"Outer\\nmut Vec<u32>\"];"),
+            "{dot}"
+        );
+        assert!(
+            dot.contains("n1 -> n0 [label=\"src/Outer.rs:4:9 (holding Vec<u32>)\"];"),
+            "{dot}",
+        );
+    }
+
+    #[test]
+    fn mermaid_labels_are_escaped() {
+        let mermaid = to_mermaid(&graph());
+
+        assert!(
+            mermaid.contains("n1[\"Outer<br/>mut Vec#lt;u32#gt;\"]"),
+            "{mermaid}",
+        );
+        assert!(
+            mermaid.contains("n1 -->|\"src/Outer.rs:4:9 (holding Vec#lt;u32#gt;)\"| n0"),
+            "{mermaid}",
+        );
+    }
+
+    #[test]
+    fn graphml_data_is_escaped() {
+        let graphml = to_graphml(&graph());
+
+        assert!(
+            graphml.contains("<data key=\"borrows\">mut Vec&lt;u32&gt;</data>"),
+            "{graphml}",
+        );
+        assert!(
+            graphml.contains("<edge id=\"e0\" source=\"n1\" target=\"n0\">"),
+            "{graphml}",
+        );
+        assert!(
+            graphml.contains("<data key=\"held\">Vec&lt;u32&gt;</data>"),
+            "{graphml}",
+        );
+    }
+}
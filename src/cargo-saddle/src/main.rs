@@ -1,6 +1,17 @@
+mod baseline;
+mod cargo;
 mod cli;
+mod config;
 mod decoder;
+mod diagnostic;
+mod diff;
+mod dwarf;
+mod elf;
+mod graph;
+mod sarif;
+mod type_name;
 mod validator;
+mod why;
 
 fn main() -> anyhow::Result<()> {
     cli::main_inner()
@@ -0,0 +1,331 @@
+//! Conversion of validator diagnostics into [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html)
+//! logs for consumption by code review tooling.
+
+use serde_json::{json, Value};
+
+use crate::diagnostic::{
+    Concurrency, ConflictKind, ContextCall, Diagnostic, Responsibility, Severity,
+};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Produces a SARIF log with one run per analyzed binary, each identified by the binary's name.
+pub fn to_sarif(runs: &[(&str, &[Diagnostic])]) -> Value {
+    let rules = [
+        ConflictKind::BorrowConflict,
+        ConflictKind::ReentrantBorrow,
+        ConflictKind::ConcurrentAccess,
+        ConflictKind::SuspendedBorrow,
+    ]
+    .map(|kind| {
+            json!({
+                "id": kind.rule_id(),
+                "shortDescription": {
+                    "text": match kind {
+                        ConflictKind::BorrowConflict =>
+                            "A scope borrows a component which one of its callers may already hold.",
+                        ConflictKind::ReentrantBorrow =>
+                            "A scope may be re-entered through a cycle while a component it borrows is held.",
+                        ConflictKind::ConcurrentAccess =>
+                            "Two scopes which may run at the same time borrow a component incompatibly.",
+                        ConflictKind::SuspendedBorrow =>
+                            "An async task may be suspended while holding a component which another task borrows.",
+                    },
+                },
+            })
+        });
+
+    let runs = runs
+        .iter()
+        .map(|&(name, diagnostics)| {
+            json!({
+                "tool": {
+                    "driver": {
+                        "name": "cargo-saddle",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "rules": rules,
+                    },
+                },
+                "automationDetails": { "id": format!("{name}/") },
+                "results": diagnostics.iter().map(to_result).collect::<Vec<_>>(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "$schema": SCHEMA,
+        "version": "2.1.0",
+        "runs": runs,
+    })
+}
+
+fn to_result(diagnostic: &Diagnostic) -> Value {
+    // Prefer pointing at the offending borrow itself, falling back to the scope's definition.
+    let borrowed_at = match &diagnostic.concurrency {
+        Some(concurrency) => concurrency
+            .accesses
+            .iter()
+            .flat_map(|access| &access.borrowed_at)
+            .collect::<Vec<_>>(),
+        None => diagnostic.explanation.nodes[0].borrowed_at.iter().collect(),
+    };
+
+    let primary = borrowed_at
+        .iter()
+        .filter(|site| site.mutability == diagnostic.mutability)
+        .chain(&borrowed_at)
+        .find_map(|site| to_location(&site.location, None))
+        .or_else(|| to_location(&diagnostic.scope.defined_at, None));
+
+    let mut related = Vec::new();
+
+    // Calls shared by several contexts are only listed once.
+    let mut context_calls = Vec::<&ContextCall>::new();
+
+    for call in diagnostic
+        .contexts
+        .iter()
+        .flat_map(|context| &context.calls)
+    {
+        if context_calls
+            .iter()
+            .any(|other| other.call_site == call.call_site && other.callee.id == call.callee.id)
+        {
+            continue;
+        }
+        context_calls.push(call);
+
+        related.extend(to_location(
+            &call.call_site,
+            Some(format!(
+                "{} calls {} here in the context in which the conflict occurs.",
+                call.caller.name, call.callee.name,
+            )),
+        ));
+    }
+
+    collect_related(&diagnostic.explanation, &mut related);
+
+    if let Some(concurrency) = &diagnostic.concurrency {
+        collect_concurrent_related(concurrency, &mut related);
+    }
+
+    for member in diagnostic.cycle.iter().flatten() {
+        for call in &member.calls {
+            related.extend(to_location(
+                &call.call_site,
+                Some(format!(
+                    "{} may call {} here as part of a cycle.",
+                    member.scope.name, call.scope.name,
+                )),
+            ));
+        }
+    }
+
+    for (i, location) in related.iter_mut().enumerate() {
+        location["id"] = json!(i);
+    }
+
+    json!({
+        "ruleId": diagnostic.kind.rule_id(),
+        "level": match diagnostic.severity {
+            Severity::Warning => "warning",
+            _ => "error",
+        },
+        "message": { "text": diagnostic.to_string() },
+        "locations": primary.into_iter().collect::<Vec<_>>(),
+        "relatedLocations": related,
+    })
+}
+
+fn collect_related(responsibility: &Responsibility, related: &mut Vec<Value>) {
+    for node in &responsibility.nodes {
+        for caller in &node.callers {
+            for call_site in &caller.call_sites {
+                related.extend(to_location(
+                    call_site,
+                    Some(format!(
+                        "{} may call {} here while the component is held {}.",
+                        responsibility.nodes[caller.node].scope.name,
+                        node.scope.name,
+                        caller.held_mutability.adjective(),
+                    )),
+                ));
+            }
+        }
+    }
+}
+
+fn collect_concurrent_related(concurrency: &Concurrency, related: &mut Vec<Value>) {
+    for site in &concurrency.spawned_at {
+        related.extend(to_location(
+            &site.location,
+            Some(format!(
+                "{} spawns {} here.",
+                site.spawner.name, site.spawned.name,
+            )),
+        ));
+    }
+
+    for access in &concurrency.other_accesses {
+        for site in &access.borrowed_at {
+            related.extend(to_location(
+                &site.location,
+                Some(format!(
+                    "{} may borrow the component {} here at the same time.",
+                    access.scope.name,
+                    site.mutability.adjective(),
+                )),
+            ));
+        }
+    }
+}
+
+/// Converts a location of the form `file:line:column` into a SARIF location. Locations which were
+/// not recorded in the binary are skipped.
+fn to_location(location: &str, message: Option<String>) -> Option<Value> {
+    let mut parts = location.rsplitn(3, ':');
+    let column = parts.next()?.parse::<u32>().ok()?;
+    let line = parts.next()?.parse::<u32>().ok()?;
+    let file = parts.next()?;
+
+    let mut location = json!({
+        "physicalLocation": {
+            "artifactLocation": { "uri": file.replace('\\', "/") },
+            "region": { "startLine": line, "startColumn": column },
+        },
+    });
+
+    if let Some(message) = message {
+        location["message"] = json!({ "text": message });
+    }
+
+    Some(location)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        diagnostic::{BorrowSite, ResponsibilityNode, ResponsibleCaller, ScopeRef},
+        validator::Mutability,
+    };
+
+    fn scope(name: &str) -> ScopeRef {
+        ScopeRef {
+            id: format!("app::{name}"),
+            name: name.to_string(),
+            defined_at: format!("src/{name}.rs:1:1"),
+        }
+    }
+
+    fn borrow_site(scope: &str, mutability: Mutability) -> BorrowSite {
+        BorrowSite {
+            mutability,
+            location: format!("src/{scope}.rs:2:5"),
+        }
+    }
+
+    /// A conflict in `inner`, which is called by `outer` while it holds the component.
+    fn conflict() -> Diagnostic {
+        Diagnostic {
+            kind: ConflictKind::BorrowConflict,
+            severity: Severity::Error,
+            scope: scope("inner"),
+            component: "u32".to_string(),
+            mutability: Mutability::Immutable,
+            held_mutability: Mutability::Mutable,
+            explanation: Responsibility {
+                nodes: vec![
+                    ResponsibilityNode {
+                        scope: scope("inner"),
+                        borrowed_at: vec![borrow_site("inner", Mutability::Immutable)],
+                        granted_at: Vec::new(),
+                        callers: vec![ResponsibleCaller {
+                            node: 1,
+                            held_mutability: Mutability::Mutable,
+                            call_sites: vec![
+                                "src/outer.rs:4:9".to_string(),
+                                "<unknown>".to_string(),
+                            ],
+                        }],
+                    },
+                    ResponsibilityNode {
+                        scope: scope("outer"),
+                        borrowed_at: vec![borrow_site("outer", Mutability::Mutable)],
+                        granted_at: Vec::new(),
+                        callers: Vec::new(),
+                    },
+                ],
+            },
+            cycle: None,
+            contexts: Vec::new(),
+            concurrency: None,
+            reached_from: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn results_point_at_the_offending_borrow() {
+        let diagnostics = [conflict()];
+        let log = to_sarif(&[("app", &diagnostics)]);
+
+        let run = &log["runs"][0];
+        assert_eq!(run["automationDetails"]["id"], "app/");
+
+        let result = &run["results"][0];
+        assert_eq!(result["ruleId"], ConflictKind::BorrowConflict.rule_id());
+        assert_eq!(result["level"], "error");
+
+        let location = &result["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "src/inner.rs");
+        assert_eq!(location["region"]["startLine"], 2);
+        assert_eq!(location["region"]["startColumn"], 5);
+    }
+
+    #[test]
+    fn unknown_call_sites_are_not_related() {
+        let diagnostics = [conflict()];
+        let log = to_sarif(&[("app", &diagnostics)]);
+
+        let related = log["runs"][0]["results"][0]["relatedLocations"]
+            .as_array()
+            .unwrap();
+        assert_eq!(related.len(), 1);
+        assert_eq!(related[0]["id"], 0);
+        assert_eq!(
+            related[0]["physicalLocation"]["artifactLocation"]["uri"],
+            "src/outer.rs",
+        );
+    }
+
+    #[test]
+    fn warnings_are_reported_as_warnings() {
+        let mut diagnostic = conflict();
+        diagnostic.severity = Severity::Warning;
+
+        let log = to_sarif(&[("app", &[diagnostic])]);
+        assert_eq!(log["runs"][0]["results"][0]["level"], "warning");
+    }
+
+    #[test]
+    fn diagnostics_serialize_to_json() {
+        let json = serde_json::to_value(conflict()).unwrap();
+
+        assert_eq!(json["kind"], "borrow_conflict");
+        assert_eq!(json["mutability"], "immutable");
+        assert_eq!(json["held_mutability"], "mutable");
+        assert_eq!(json["scope"]["name"], "inner");
+        assert_eq!(json["explanation"]["nodes"][0]["callers"][0]["node"], 1);
+    }
+
+    #[test]
+    fn windows_paths_use_forward_slashes() {
+        let location = to_location(r"src\bin\main.rs:3:7", None).unwrap();
+        assert_eq!(
+            location["physicalLocation"]["artifactLocation"]["uri"],
+            "src/bin/main.rs",
+        );
+        assert!(to_location("<unknown>", None).is_none());
+    }
+}
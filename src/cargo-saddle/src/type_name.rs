@@ -0,0 +1,926 @@
+//! A parser for the strings produced by `core::any::type_name`.
+//!
+//! The output of `type_name` is not guaranteed to be stable but, in practice, it is the type
+//! printed as Rust syntax with fully qualified paths. This module parses that syntax into a small
+//! AST which can be printed back out in a canonical form.
+
+use std::fmt::{self, Write};
+
+// === AST === //
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum Ty {
+    /// A (possibly generic) path such as `alloc::vec::Vec<u32>` or `demo::main::{{closure}}`.
+    Path(Path),
+    /// A qualified path such as `<T as core::ops::Deref>::Target`.
+    QualifiedPath {
+        self_ty: Box<Ty>,
+        as_trait: Option<Path>,
+        rest: Vec<PathSegment>,
+    },
+    Ref {
+        lifetime: Option<String>,
+        mutable: bool,
+        pointee: Box<Ty>,
+    },
+    Ptr {
+        mutable: bool,
+        pointee: Box<Ty>,
+    },
+    Slice(Box<Ty>),
+    Array(Box<Ty>, ConstArg),
+    Tuple(Vec<Ty>),
+    /// A type wrapped in parentheses to disambiguate its bounds, e.g. `&(dyn A + B)`.
+    Paren(Box<Ty>),
+    Never,
+    Dyn(Vec<Bound>),
+    Impl(Vec<Bound>),
+    FnPtr(FnPtr),
+}
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct Path {
+    pub segments: Vec<PathSegment>,
+}
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct PathSegment {
+    pub name: String,
+    pub args: PathArgs,
+}
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum PathArgs {
+    None,
+    /// Angle-bracketed arguments such as `<T, 3>`.
+    AngleBracketed(Vec<GenericArg>),
+    /// The parenthesized sugar of the `Fn*` traits such as `(u32) -> u32`.
+    Parenthesized {
+        inputs: Vec<Ty>,
+        output: Option<Box<Ty>>,
+    },
+}
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum GenericArg {
+    Lifetime(String),
+    Type(Ty),
+    Const(ConstArg),
+    /// An associated type binding such as `Output = u32`.
+    Binding(String, Ty),
+}
+
+/// A const generic argument, kept in its printed form.
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct ConstArg(pub String);
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum Bound {
+    Lifetime(String),
+    Trait {
+        for_lifetimes: Vec<String>,
+        maybe: bool,
+        path: Path,
+    },
+}
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct FnPtr {
+    pub for_lifetimes: Vec<String>,
+    pub is_unsafe: bool,
+    pub abi: Option<String>,
+    pub inputs: Vec<Ty>,
+    pub is_variadic: bool,
+    pub output: Option<Box<Ty>>,
+}
+
+impl Path {
+    pub fn last(&self) -> &PathSegment {
+        self.segments.last().unwrap()
+    }
+}
+
+impl PathSegment {
+    pub fn generic_args(&self) -> &[GenericArg] {
+        match &self.args {
+            PathArgs::AngleBracketed(args) => args,
+            _ => &[],
+        }
+    }
+}
+
+impl Ty {
+    pub fn as_path(&self) -> Option<&Path> {
+        match self {
+            Ty::Path(path) => Some(path),
+            _ => None,
+        }
+    }
+}
+
+impl GenericArg {
+    pub fn as_ty(&self) -> Option<&Ty> {
+        match self {
+            GenericArg::Type(ty) => Some(ty),
+            _ => None,
+        }
+    }
+
+    pub fn as_const(&self) -> Option<&str> {
+        match self {
+            GenericArg::Const(ConstArg(value)) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+// === Printing === //
+
+fn write_list<T: fmt::Display>(f: &mut fmt::Formatter<'_>, items: &[T], sep: &str) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            f.write_str(sep)?;
+        }
+        write!(f, "{item}")?;
+    }
+    Ok(())
+}
+
+fn write_for_lifetimes(f: &mut fmt::Formatter<'_>, lifetimes: &[String]) -> fmt::Result {
+    if !lifetimes.is_empty() {
+        f.write_str("for<")?;
+        write_list(f, lifetimes, ", ")?;
+        f.write_str("> ")?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ty::Path(path) => write!(f, "{path}"),
+            Ty::QualifiedPath {
+                self_ty,
+                as_trait,
+                rest,
+            } => {
+                write!(f, "<{self_ty}")?;
+                if let Some(as_trait) = as_trait {
+                    write!(f, " as {as_trait}")?;
+                }
+                f.write_char('>')?;
+                for segment in rest {
+                    write!(f, "::{segment}")?;
+                }
+                Ok(())
+            }
+            Ty::Ref {
+                lifetime,
+                mutable,
+                pointee,
+            } => {
+                f.write_char('&')?;
+                if let Some(lifetime) = lifetime {
+                    write!(f, "{lifetime} ")?;
+                }
+                if *mutable {
+                    f.write_str("mut ")?;
+                }
+                write!(f, "{pointee}")
+            }
+            Ty::Ptr { mutable, pointee } => {
+                let kw = if *mutable { "mut" } else { "const" };
+                write!(f, "*{kw} {pointee}")
+            }
+            Ty::Slice(elem) => write!(f, "[{elem}]"),
+            Ty::Array(elem, len) => write!(f, "[{elem}; {len}]"),
+            Ty::Tuple(elems) => {
+                f.write_char('(')?;
+                write_list(f, elems, ", ")?;
+                if elems.len() == 1 {
+                    f.write_char(',')?;
+                }
+                f.write_char(')')
+            }
+            Ty::Paren(inner) => write!(f, "({inner})"),
+            Ty::Never => f.write_char('!'),
+            Ty::Dyn(bounds) => {
+                f.write_str("dyn ")?;
+                write_list(f, bounds, " + ")
+            }
+            Ty::Impl(bounds) => {
+                f.write_str("impl ")?;
+                write_list(f, bounds, " + ")
+            }
+            Ty::FnPtr(fn_ptr) => write!(f, "{fn_ptr}"),
+        }
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_list(f, &self.segments, "::")
+    }
+}
+
+impl fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        match &self.args {
+            PathArgs::None => Ok(()),
+            PathArgs::AngleBracketed(args) => {
+                f.write_char('<')?;
+                write_list(f, args, ", ")?;
+                f.write_char('>')
+            }
+            PathArgs::Parenthesized { inputs, output } => {
+                f.write_char('(')?;
+                write_list(f, inputs, ", ")?;
+                f.write_char(')')?;
+                if let Some(output) = output {
+                    write!(f, " -> {output}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for GenericArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenericArg::Lifetime(lifetime) => f.write_str(lifetime),
+            GenericArg::Type(ty) => write!(f, "{ty}"),
+            GenericArg::Const(value) => write!(f, "{value}"),
+            GenericArg::Binding(name, ty) => write!(f, "{name} = {ty}"),
+        }
+    }
+}
+
+impl fmt::Display for ConstArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Display for Bound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bound::Lifetime(lifetime) => f.write_str(lifetime),
+            Bound::Trait {
+                for_lifetimes,
+                maybe,
+                path,
+            } => {
+                write_for_lifetimes(f, for_lifetimes)?;
+                if *maybe {
+                    f.write_char('?')?;
+                }
+                write!(f, "{path}")
+            }
+        }
+    }
+}
+
+impl fmt::Display for FnPtr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_for_lifetimes(f, &self.for_lifetimes)?;
+        if self.is_unsafe {
+            f.write_str("unsafe ")?;
+        }
+        if let Some(abi) = &self.abi {
+            write!(f, "extern \"{abi}\" ")?;
+        }
+        f.write_str("fn(")?;
+        write_list(f, &self.inputs, ", ")?;
+        if self.is_variadic {
+            if !self.inputs.is_empty() {
+                f.write_str(", ")?;
+            }
+            f.write_str("...")?;
+        }
+        f.write_char(')')?;
+        if let Some(output) = &self.output {
+            write!(f, " -> {output}")?;
+        }
+        Ok(())
+    }
+}
+
+// === Parser === //
+
+/// A recursive-descent parser over the bytes of a type name. Parsing operates on bytes rather than
+/// `str`s so that a type name can be parsed directly out of a larger binary, stopping at the end
+/// of the type.
+pub struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(src: &'a [u8]) -> Self {
+        Self { src, pos: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        &self.src[self.pos.min(self.src.len())..]
+    }
+
+    fn skip_ws(&mut self) {
+        while self.peek() == Some(b' ') {
+            self.pos += 1;
+        }
+    }
+
+    fn error<T>(&self, expected: &str) -> anyhow::Result<T> {
+        anyhow::bail!("expected {expected} at offset {} of type name", self.pos)
+    }
+
+    /// Consumes the punctuation `tok` after skipping whitespace.
+    pub fn eat(&mut self, tok: &str) -> bool {
+        self.skip_ws();
+        if self.rest().starts_with(tok.as_bytes()) {
+            self.pos += tok.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn expect(&mut self, tok: &str) -> anyhow::Result<()> {
+        if self.eat(tok) {
+            Ok(())
+        } else {
+            self.error(&format!("`{tok}`"))
+        }
+    }
+
+    /// Consumes the keyword `kw` if it is not immediately followed by more identifier characters.
+    fn eat_keyword(&mut self, kw: &str) -> bool {
+        self.skip_ws();
+        let rest = self.rest();
+        if rest.starts_with(kw.as_bytes())
+            && !rest
+                .get(kw.len())
+                .is_some_and(|&c| is_ident_continue(c) || c == b'{')
+        {
+            self.pos += kw.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn peek_keyword(&mut self, kw: &str) -> bool {
+        let start = self.pos;
+        let found = self.eat_keyword(kw);
+        self.pos = start;
+        found
+    }
+
+    fn take_while(&mut self, mut pred: impl FnMut(u8) -> bool) -> &'a [u8] {
+        let start = self.pos;
+        while self.peek().is_some_and(&mut pred) {
+            self.pos += 1;
+        }
+        &self.src[start..self.pos]
+    }
+
+    fn utf8(&self, bytes: &[u8]) -> anyhow::Result<String> {
+        match std::str::from_utf8(bytes) {
+            Ok(str) => Ok(str.to_string()),
+            Err(_) => self.error("valid UTF-8"),
+        }
+    }
+
+    pub fn parse_ty(&mut self) -> anyhow::Result<Ty> {
+        self.skip_ws();
+
+        if self.eat("!") {
+            return Ok(Ty::Never);
+        }
+
+        if self.eat("&") {
+            let lifetime = self.parse_lifetime()?;
+            let mutable = self.eat_keyword("mut");
+            let pointee = Box::new(self.parse_ty()?);
+            return Ok(Ty::Ref {
+                lifetime,
+                mutable,
+                pointee,
+            });
+        }
+
+        if self.eat("*") {
+            let mutable = if self.eat_keyword("mut") {
+                true
+            } else if self.eat_keyword("const") {
+                false
+            } else {
+                return self.error("`const` or `mut`");
+            };
+            let pointee = Box::new(self.parse_ty()?);
+            return Ok(Ty::Ptr { mutable, pointee });
+        }
+
+        if self.eat("[") {
+            let elem = Box::new(self.parse_ty()?);
+            return if self.eat(";") {
+                let len = self.parse_const_arg()?;
+                self.expect("]")?;
+                Ok(Ty::Array(elem, len))
+            } else {
+                self.expect("]")?;
+                Ok(Ty::Slice(elem))
+            };
+        }
+
+        if self.eat("(") {
+            let mut elems = Vec::new();
+            let mut saw_comma = false;
+
+            while !self.eat(")") {
+                elems.push(self.parse_ty()?);
+                if self.eat(",") {
+                    saw_comma = true;
+                } else {
+                    self.expect(")")?;
+                    break;
+                }
+            }
+
+            return if elems.len() == 1 && !saw_comma {
+                Ok(Ty::Paren(Box::new(elems.pop().unwrap())))
+            } else {
+                Ok(Ty::Tuple(elems))
+            };
+        }
+
+        if self.eat_keyword("dyn") {
+            return Ok(Ty::Dyn(self.parse_bounds()?));
+        }
+
+        if self.eat_keyword("impl") {
+            return Ok(Ty::Impl(self.parse_bounds()?));
+        }
+
+        if self.peek_keyword("for")
+            || self.peek_keyword("fn")
+            || self.peek_keyword("unsafe")
+            || self.peek_keyword("extern")
+        {
+            let for_lifetimes = self.parse_for_lifetimes()?;
+
+            // `for<'a> Trait` can only appear as a bound and bounds are handled elsewhere so this
+            // must be a function pointer.
+            return Ok(Ty::FnPtr(self.parse_fn_ptr(for_lifetimes)?));
+        }
+
+        if self.eat("<") {
+            let self_ty = Box::new(self.parse_ty()?);
+            let as_trait = if self.eat_keyword("as") {
+                Some(self.parse_path()?)
+            } else {
+                None
+            };
+            self.expect(">")?;
+
+            let mut rest = Vec::new();
+            while self.eat("::") {
+                rest.push(self.parse_path_segment()?);
+            }
+
+            return Ok(Ty::QualifiedPath {
+                self_ty,
+                as_trait,
+                rest,
+            });
+        }
+
+        Ok(Ty::Path(self.parse_path()?))
+    }
+
+    fn parse_fn_ptr(&mut self, for_lifetimes: Vec<String>) -> anyhow::Result<FnPtr> {
+        let is_unsafe = self.eat_keyword("unsafe");
+
+        let abi = if self.eat_keyword("extern") {
+            self.expect("\"")?;
+            let abi = self.take_while(|c| c != b'"');
+            let abi = self.utf8(abi)?;
+            self.expect("\"")?;
+            Some(abi)
+        } else {
+            None
+        };
+
+        if !self.eat_keyword("fn") {
+            return self.error("`fn`");
+        }
+
+        self.expect("(")?;
+        let mut inputs = Vec::new();
+        let mut is_variadic = false;
+
+        while !self.eat(")") {
+            if self.eat("...") {
+                is_variadic = true;
+            } else {
+                inputs.push(self.parse_ty()?);
+            }
+
+            if !self.eat(",") {
+                self.expect(")")?;
+                break;
+            }
+        }
+
+        let output = self.parse_fn_output()?;
+
+        Ok(FnPtr {
+            for_lifetimes,
+            is_unsafe,
+            abi,
+            inputs,
+            is_variadic,
+            output,
+        })
+    }
+
+    fn parse_fn_output(&mut self) -> anyhow::Result<Option<Box<Ty>>> {
+        if self.eat("->") {
+            Ok(Some(Box::new(self.parse_ty()?)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_for_lifetimes(&mut self) -> anyhow::Result<Vec<String>> {
+        let mut lifetimes = Vec::new();
+
+        if self.eat_keyword("for") {
+            self.expect("<")?;
+            while !self.eat(">") {
+                match self.parse_lifetime()? {
+                    Some(lifetime) => lifetimes.push(lifetime),
+                    None => return self.error("a lifetime"),
+                }
+                if !self.eat(",") {
+                    self.expect(">")?;
+                    break;
+                }
+            }
+        }
+
+        Ok(lifetimes)
+    }
+
+    fn parse_bounds(&mut self) -> anyhow::Result<Vec<Bound>> {
+        let mut bounds = Vec::new();
+
+        loop {
+            if let Some(lifetime) = self.parse_lifetime()? {
+                bounds.push(Bound::Lifetime(lifetime));
+            } else {
+                let for_lifetimes = self.parse_for_lifetimes()?;
+                let maybe = self.eat("?");
+                let path = self.parse_path()?;
+                bounds.push(Bound::Trait {
+                    for_lifetimes,
+                    maybe,
+                    path,
+                });
+            }
+
+            if !self.eat("+") {
+                break;
+            }
+        }
+
+        Ok(bounds)
+    }
+
+    /// Parses a lifetime if one is present. Character literals (`'a'`) are not lifetimes.
+    fn parse_lifetime(&mut self) -> anyhow::Result<Option<String>> {
+        self.skip_ws();
+        if self.peek() != Some(b'\'') {
+            return Ok(None);
+        }
+
+        let start = self.pos;
+        self.pos += 1;
+        let name = self.take_while(is_ident_continue);
+
+        if name.is_empty() || self.peek() == Some(b'\'') {
+            self.pos = start;
+            return Ok(None);
+        }
+
+        Ok(Some(self.utf8(&self.src[start..self.pos])?))
+    }
+
+    fn parse_path(&mut self) -> anyhow::Result<Path> {
+        // Paths printed by `type_name` are never prefixed with `::` but we accept it anyways.
+        self.eat("::");
+
+        let mut segments = vec![self.parse_path_segment()?];
+
+        loop {
+            // Avoid consuming the `::` of a qualified path's trailing segments which we don't own.
+            let start = self.pos;
+            if !self.eat("::") {
+                break;
+            }
+
+            self.skip_ws();
+            if self.peek() == Some(b'<') {
+                // A turbofish, e.g. `Vec::<u32>`.
+                segments.last_mut().unwrap().args =
+                    PathArgs::AngleBracketed(self.parse_generic_args()?);
+                continue;
+            }
+
+            if !self.peek().is_some_and(|c| is_ident_start(c) || c == b'{') {
+                self.pos = start;
+                break;
+            }
+
+            segments.push(self.parse_path_segment()?);
+        }
+
+        Ok(Path { segments })
+    }
+
+    fn parse_path_segment(&mut self) -> anyhow::Result<PathSegment> {
+        let name = self.parse_segment_name()?;
+
+        // Generic arguments must follow the segment name directly. Otherwise, we'd mistake the
+        // comparison-like `>` of an enclosing argument list for our own.
+        let args = if self.peek() == Some(b'<') {
+            PathArgs::AngleBracketed(self.parse_generic_args()?)
+        } else if self.peek() == Some(b'(') && is_fn_trait_name(&name) {
+            self.expect("(")?;
+            let mut inputs = Vec::new();
+            while !self.eat(")") {
+                inputs.push(self.parse_ty()?);
+                if !self.eat(",") {
+                    self.expect(")")?;
+                    break;
+                }
+            }
+            let output = self.parse_fn_output()?;
+            PathArgs::Parenthesized { inputs, output }
+        } else {
+            PathArgs::None
+        };
+
+        Ok(PathSegment { name, args })
+    }
+
+    /// Parses an identifier or one of the compiler-generated segments such as `{{closure}}`,
+    /// `{{constant}}`, or `{closure#0}`.
+    fn parse_segment_name(&mut self) -> anyhow::Result<String> {
+        self.skip_ws();
+        let start = self.pos;
+
+        if self.peek() == Some(b'{') {
+            let mut depth = 0;
+            while let Some(c) = self.peek() {
+                self.pos += 1;
+                match c {
+                    b'{' => depth += 1,
+                    b'}' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            if depth != 0 {
+                return self.error("`}`");
+            }
+        } else {
+            if self.rest().starts_with(b"r#") {
+                self.pos += 2;
+            }
+
+            if !self.peek().is_some_and(is_ident_start) {
+                return self.error("an identifier");
+            }
+            self.take_while(is_ident_continue);
+        }
+
+        self.utf8(&self.src[start..self.pos])
+    }
+
+    /// Parses a `<...>` generic argument list.
+    pub fn parse_generic_args(&mut self) -> anyhow::Result<Vec<GenericArg>> {
+        self.expect("<")?;
+        let mut args = Vec::new();
+
+        while !self.eat(">") {
+            args.push(self.parse_generic_arg()?);
+            if !self.eat(",") {
+                self.expect(">")?;
+                break;
+            }
+        }
+
+        Ok(args)
+    }
+
+    fn parse_generic_arg(&mut self) -> anyhow::Result<GenericArg> {
+        self.skip_ws();
+
+        if let Some(lifetime) = self.parse_lifetime()? {
+            return Ok(GenericArg::Lifetime(lifetime));
+        }
+
+        if self.peek().is_some_and(|c| {
+            c.is_ascii_digit() || c == b'-' || c == b'{' && !self.rest().starts_with(b"{{")
+        }) || self.peek() == Some(b'\'')
+            || self.peek_keyword("true")
+            || self.peek_keyword("false")
+        {
+            return Ok(GenericArg::Const(self.parse_const_arg()?));
+        }
+
+        // Associated type bindings, e.g. `Output = u32`.
+        let start = self.pos;
+        if self.peek().is_some_and(is_ident_start) {
+            let name = self.take_while(is_ident_continue);
+            if self.eat("=") && !self.rest().starts_with(b"=") {
+                let name = self.utf8(name)?;
+                return Ok(GenericArg::Binding(name, self.parse_ty()?));
+            }
+            self.pos = start;
+        }
+
+        Ok(GenericArg::Type(self.parse_ty()?))
+    }
+
+    fn parse_const_arg(&mut self) -> anyhow::Result<ConstArg> {
+        self.skip_ws();
+        let start = self.pos;
+
+        match self.peek() {
+            // Character literals, e.g. `'a'` or `'\''`.
+            Some(b'\'') => {
+                self.pos += 1;
+                while let Some(c) = self.peek() {
+                    self.pos += 1;
+                    match c {
+                        b'\\' => self.pos += 1,
+                        b'\'' => break,
+                        _ => {}
+                    }
+                }
+            }
+            // Unevaluated constant expressions.
+            Some(b'{') => {
+                let mut depth = 0;
+                while let Some(c) = self.peek() {
+                    self.pos += 1;
+                    match c {
+                        b'{' => depth += 1,
+                        b'}' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            // Integers (possibly with a type suffix) and booleans.
+            _ => {
+                self.eat("-");
+                self.take_while(|c| c.is_ascii_alphanumeric() || c == b'_');
+            }
+        }
+
+        if self.pos == start {
+            return self.error("a const generic argument");
+        }
+
+        Ok(ConstArg(self.utf8(&self.src[start..self.pos])?))
+    }
+}
+
+fn is_ident_start(c: u8) -> bool {
+    c.is_ascii_alphabetic() || c == b'_' || c >= 0x80
+}
+
+fn is_ident_continue(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_' || c >= 0x80
+}
+
+fn is_fn_trait_name(name: &str) -> bool {
+    matches!(
+        name,
+        "Fn" | "FnMut" | "FnOnce" | "AsyncFn" | "AsyncFnMut" | "AsyncFnOnce"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+
+    use std::{any::type_name, marker::PhantomData};
+
+    use super::*;
+
+    struct Wrapper<T: ?Sized, const N: usize>(PhantomData<T>);
+
+    fn parse(src: &str) -> Ty {
+        let mut parser = Parser::new(src.as_bytes());
+        let ty = parser.parse_ty().unwrap();
+        assert_eq!(parser.position(), src.len(), "trailing input in {src:?}");
+        ty
+    }
+
+    #[track_caller]
+    fn round_trip(src: &str) -> Ty {
+        let ty = parse(src);
+        assert_eq!(ty.to_string(), src);
+        ty
+    }
+
+    #[test]
+    fn tuples() {
+        assert_eq!(round_trip("()"), Ty::Tuple(Vec::new()));
+        assert!(matches!(round_trip("(u32,)"), Ty::Tuple(tys) if tys.len() == 1));
+        assert!(matches!(round_trip("(u32, i64)"), Ty::Tuple(tys) if tys.len() == 2));
+        round_trip(type_name::<(u8, (alloc::string::String, ()), [u16; 3])>());
+    }
+
+    #[test]
+    fn dyn_traits() {
+        let ty = round_trip("dyn core::fmt::Debug + core::marker::Send");
+        assert!(matches!(&ty, Ty::Dyn(bounds) if bounds.len() == 2));
+
+        round_trip("&(dyn core::any::Any + 'static)");
+        round_trip("alloc::boxed::Box<dyn core::ops::function::Fn(u32) -> u32>");
+        round_trip(type_name::<dyn std::fmt::Display>());
+        round_trip(type_name::<Box<dyn FnMut(&str) -> bool + Send>>());
+    }
+
+    #[test]
+    fn static_str() {
+        let ty = round_trip("&'static str");
+        let Ty::Ref {
+            lifetime,
+            mutable,
+            pointee,
+        } = ty
+        else {
+            panic!("expected a reference");
+        };
+
+        assert_eq!(lifetime.as_deref(), Some("'static"));
+        assert!(!mutable);
+        assert_eq!(pointee.as_path().unwrap().last().name, "str");
+    }
+
+    #[test]
+    fn closure_paths() {
+        let ty = round_trip("demo::main::{{closure}}");
+        let path = ty.as_path().unwrap();
+        assert_eq!(path.segments.len(), 3);
+        assert_eq!(path.last().name, "{{closure}}");
+
+        round_trip("demo::main::{{closure}}::{{closure}}");
+        round_trip("alloc::vec::Vec<demo::run::{{closure}}>");
+
+        fn name_of<T>(_: &T) -> &'static str {
+            type_name::<T>()
+        }
+
+        round_trip(name_of(&|| ()));
+    }
+
+    #[test]
+    fn const_generics() {
+        let ty = round_trip("demo::Wrapper<u32, 3>");
+        let args = ty.as_path().unwrap().last().generic_args();
+        assert_eq!(args[0].as_ty().unwrap().to_string(), "u32");
+        assert_eq!(args[1].as_const(), Some("3"));
+
+        round_trip("[u8; 16]");
+        round_trip("demo::Flag<true>");
+        round_trip("demo::Offset<-1>");
+        round_trip(type_name::<Wrapper<str, 7>>());
+        round_trip(type_name::<Wrapper<[&'static str; 2], 0>>());
+    }
+}
@@ -1,34 +1,22 @@
-use petgraph::{algo::toposort, stable_graph::NodeIndex, visit::EdgeRef, Direction};
+use anyhow::Context;
+use petgraph::{
+    graph::EdgeReference,
+    stable_graph::{EdgeIndex, NodeIndex},
+    visit::EdgeRef,
+    Direction,
+};
 use rustc_hash::{FxHashMap, FxHashSet};
-use std::fmt::{self, Write};
-
-// === Helpers === //
-
-const INDENT_SIZE: u32 = 4;
-
-#[derive(Copy, Clone)]
-struct Indent(u32);
-
-impl fmt::Display for Indent {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for _ in 0..self.0 {
-            f.write_char(' ')?;
-        }
-        Ok(())
-    }
-}
-
-fn borrow_two<T>(list: &mut [T], a: usize, b: usize) -> (&mut T, &mut T) {
-    assert_ne!(a, b);
-
-    if a < b {
-        let (left, right) = list.split_at_mut(a + 1);
-        (&mut left[a], &mut right[b - a - 1])
-    } else {
-        let (b, a) = borrow_two(list, b, a);
-        (a, b)
-    }
-}
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::{
+    config::{Config, Lints},
+    diagnostic::{
+        BorrowSite, CallingContext, Concurrency, ConflictKind, ContextCall, CycleCall, CycleMember,
+        Diagnostic, Responsibility, ResponsibilityNode, ResponsibleCaller, ScopeKeys, ScopeRef,
+        Severity, SpawnSite, TaskAccess, UnreachedScope, UNKNOWN_LOCATION,
+    },
+};
 
 // === Definitions === //
 
@@ -54,6 +42,16 @@ pub struct ComponentMeta<'a> {
 #[derive(Debug, Copy, Clone)]
 pub struct CallMeta<'a> {
     pub def_path: &'a str,
+    /// The components of the caller which remain borrowed during the call. If this is `None`,
+    /// every component borrowed by the caller is assumed to be held.
+    pub held: Option<&'a [String]>,
+}
+
+impl CallMeta<'_> {
+    /// Whether the caller's own borrow of `component` is held during the call.
+    fn holds(&self, component: &ComponentId<'_>) -> bool {
+        self.held.is_none_or(|held| held.contains(&component.0))
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -62,7 +60,10 @@ pub struct BorrowMeta<'a> {
     pub mutability: Mutability,
 }
 
-#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Default)]
+#[derive(
+    Debug, Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
 pub enum Mutability {
     #[default]
     Immutable,
@@ -77,6 +78,13 @@ impl Mutability {
         }
     }
 
+    pub fn keyword(self) -> &'static str {
+        match self {
+            Mutability::Mutable => "mut",
+            Mutability::Immutable => "ref",
+        }
+    }
+
     pub fn is_compatible_with(self, other: Mutability) -> bool {
         use Mutability::*;
         matches!((self, other), (Immutable, Immutable))
@@ -92,373 +100,2798 @@ impl Mutability {
     }
 }
 
+/// Determines whether the path `name` is `query` or ends with the path segments in `query`.
+fn path_matches(name: &str, query: &str) -> bool {
+    name == query
+        || name
+            .strip_suffix(query)
+            .is_some_and(|prefix| prefix.ends_with("::"))
+}
+
 // === Validator === //
 
+fn push_unique_meta<'a>(metas: &mut Vec<BorrowMeta<'a>>, meta: BorrowMeta<'a>) {
+    if !metas
+        .iter()
+        .any(|other| other.def_path == meta.def_path && other.mutability == meta.mutability)
+    {
+        metas.push(meta);
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct Validator<'a> {
     call_graph: petgraph::Graph<Scope<'a>, CallMeta<'a>>,
     scope_id_to_node: FxHashMap<ScopeId<'a>, NodeIndex>,
     component_meta: FxHashMap<ComponentId<'a>, ComponentMeta<'a>>,
+    dispatch_sites: FxHashMap<NodeIndex, Vec<(NodeIndex, CallMeta<'a>)>>,
+    dispatch_impls: FxHashMap<NodeIndex, Vec<NodeIndex>>,
+    call_edges: FxHashSet<(NodeIndex, NodeIndex, &'a str, Option<&'a [String]>)>,
+    spawns: FxHashMap<NodeIndex, Vec<(NodeIndex, CallMeta<'a>)>>,
+    task_roots: FxHashSet<NodeIndex>,
+    definitions: FxHashMap<&'a str, &'a str>,
+    entry_points: FxHashMap<NodeIndex, Vec<&'a str>>,
+    root_names: Vec<String>,
+    roots: FxHashSet<NodeIndex>,
+    ignored_components: Vec<String>,
+    lints: Lints,
+    context_depth: usize,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 struct Scope<'a> {
+    id: String,
     borrows: FxHashMap<ComponentId<'a>, (Mutability, Vec<BorrowMeta<'a>>)>,
+    /// Borrows which are released before the scope calls into any other scope. These are checked
+    /// against the scope's PBS but never propagated to its callees.
+    transient_borrows: FxHashMap<ComponentId<'a>, (Mutability, Vec<BorrowMeta<'a>>)>,
     grants: FxHashMap<ComponentId<'a>, (Mutability, Vec<BorrowMeta<'a>>)>,
-    meta: Option<ScopeMeta<'a>>,
+    /// The subset of `borrows` which may be held while the scope's async task is suspended.
+    holds: FxHashMap<ComponentId<'a>, (Mutability, Vec<BorrowMeta<'a>>)>,
+    meta: ScopeMeta<'a>,
+}
+
+impl<'a> Scope<'a> {
+    /// Every borrow made by the scope, whether it is held or transient.
+    fn all_borrows(
+        &self,
+    ) -> impl Iterator<Item = (&ComponentId<'a>, &(Mutability, Vec<BorrowMeta<'a>>))> {
+        self.borrows.iter().chain(&self.transient_borrows)
+    }
 }
 
 impl<'a> Validator<'a> {
-    fn get_scope_node(&mut self, scope: ScopeId<'a>) -> NodeIndex {
-        *self
-            .scope_id_to_node
-            .entry(scope)
-            .or_insert_with(|| self.call_graph.add_node(Scope::default()))
+    pub fn new(config: &Config) -> Self {
+        Self {
+            root_names: config.roots.clone(),
+            ignored_components: config.ignored_components.clone(),
+            lints: config.lints,
+            ..Self::default()
+        }
+    }
+
+    /// Analyzes every scope separately for each sequence of up to `depth` calls through which it
+    /// may be entered, reporting conflicts per calling context. A depth of zero merges all of the
+    /// contexts of each scope, which is the default.
+    pub fn set_context_depth(&mut self, depth: usize) {
+        self.context_depth = depth;
     }
 
-    pub fn push_call_edge(&mut self, from: ScopeId<'a>, to: ScopeId<'a>, meta: CallMeta<'a>) {
-        let from_idx = self.get_scope_node(from);
-        let to_idx = self.get_scope_node(to);
+    /// Records that a scope named `name` is defined at `defined_at`. Once any definition has been
+    /// registered, scopes registered with [`Validator::register_scope`] which do not correspond to
+    /// any of them are listed by [`Validator::undefined_scopes`].
+    pub fn register_definition(&mut self, name: &'a str, defined_at: &'a str) {
+        self.definitions.insert(defined_at, name);
+    }
+
+    /// Registers a scope so that it can be referenced by the `push_*` methods.
+    pub fn register_scope(&mut self, scope: ScopeId<'a>, meta: ScopeMeta<'a>) {
+        if !self.scope_id_to_node.contains_key(&scope) {
+            let is_root = self
+                .root_names
+                .iter()
+                .any(|root| path_matches(&meta.name, root));
+
+            let idx = self.call_graph.add_node(Scope {
+                id: scope.0.clone(),
+                borrows: FxHashMap::default(),
+                transient_borrows: FxHashMap::default(),
+                grants: FxHashMap::default(),
+                holds: FxHashMap::default(),
+                meta,
+            });
+
+            self.scope_id_to_node.insert(scope, idx);
+
+            if is_root {
+                self.roots.insert(idx);
+            }
+        }
+    }
+
+    fn get_scope_node(&self, scope: &ScopeId<'a>) -> anyhow::Result<NodeIndex> {
+        self.scope_id_to_node
+            .get(scope)
+            .copied()
+            .with_context(|| format!("The binary references the unknown scope {}.", scope.0))
+    }
+
+    /// Adds a call edge to the graph unless an identical one has already been declared. The same
+    /// declaration is often emitted several times into a binary (e.g. once per monomorphization),
+    /// so this keeps those copies from being reported more than once.
+    fn add_call_edge(&mut self, from_idx: NodeIndex, to_idx: NodeIndex, meta: CallMeta<'a>) {
+        if self
+            .call_edges
+            .insert((from_idx, to_idx, meta.def_path, meta.held))
+        {
+            self.call_graph.add_edge(from_idx, to_idx, meta);
+        }
+    }
+
+    pub fn push_call_edge(
+        &mut self,
+        from: &ScopeId<'a>,
+        to: &ScopeId<'a>,
+        meta: CallMeta<'a>,
+    ) -> anyhow::Result<()> {
+        let from_idx = self.get_scope_node(from)?;
+        let to_idx = self.get_scope_node(to)?;
+
+        self.add_call_edge(from_idx, to_idx, meta);
+        Ok(())
+    }
+
+    /// Records that `from` may dispatch dynamically through the dispatch point `point`. This is
+    /// treated as a call into the dispatch point itself and as a call into every scope which
+    /// implements it, regardless of the order in which the two were declared.
+    pub fn push_dispatch_edge(
+        &mut self,
+        from: &ScopeId<'a>,
+        point: &ScopeId<'a>,
+        meta: CallMeta<'a>,
+    ) -> anyhow::Result<()> {
+        let from_idx = self.get_scope_node(from)?;
+        let point_idx = self.get_scope_node(point)?;
+
+        if !self
+            .call_edges
+            .contains(&(from_idx, point_idx, meta.def_path, meta.held))
+        {
+            self.dispatch_sites
+                .entry(point_idx)
+                .or_default()
+                .push((from_idx, meta));
+        }
+
+        self.add_call_edge(from_idx, point_idx, meta);
+
+        for impl_idx in self
+            .dispatch_impls
+            .get(&point_idx)
+            .cloned()
+            .unwrap_or_default()
+        {
+            self.add_call_edge(from_idx, impl_idx, meta);
+        }
+
+        Ok(())
+    }
+
+    /// Records that `from` may spawn `to` on another thread. Spawns are kept out of the call graph
+    /// since the spawner's borrows are not held on behalf of the spawned scope.
+    pub fn push_spawn_edge(
+        &mut self,
+        from: &ScopeId<'a>,
+        to: &ScopeId<'a>,
+        meta: CallMeta<'a>,
+    ) -> anyhow::Result<()> {
+        let from_idx = self.get_scope_node(from)?;
+        let to_idx = self.get_scope_node(to)?;
+
+        let spawns = self.spawns.entry(from_idx).or_default();
+        if !spawns
+            .iter()
+            .any(|&(other, other_meta)| other == to_idx && other_meta.def_path == meta.def_path)
+        {
+            spawns.push((to_idx, meta));
+        }
+
+        Ok(())
+    }
+
+    pub fn push_impl(&mut self, scope: &ScopeId<'a>, point: &ScopeId<'a>) -> anyhow::Result<()> {
+        let scope_idx = self.get_scope_node(scope)?;
+        let point_idx = self.get_scope_node(point)?;
+
+        let impls = self.dispatch_impls.entry(point_idx).or_default();
+        if impls.contains(&scope_idx) {
+            return Ok(());
+        }
+        impls.push(scope_idx);
+
+        for (site_idx, meta) in self
+            .dispatch_sites
+            .get(&point_idx)
+            .cloned()
+            .unwrap_or_default()
+        {
+            self.add_call_edge(site_idx, scope_idx, meta);
+        }
 
-        self.call_graph.add_edge(from_idx, to_idx, meta);
+        Ok(())
     }
 
     pub fn push_access(
         &mut self,
-        scope: ScopeId<'a>,
+        scope: &ScopeId<'a>,
         component: ComponentId<'a>,
         req_access: Mutability,
         meta: BorrowMeta<'a>,
-    ) {
-        let scope_idx = self.get_scope_node(scope);
+    ) -> anyhow::Result<()> {
+        let scope_idx = self.get_scope_node(scope)?;
+        if self.is_ignored(&component) {
+            return Ok(());
+        }
+
         let (curr_access, metas) = self.call_graph[scope_idx]
             .borrows
             .entry(component)
-            .or_insert_with(Default::default);
+            .or_default();
 
         *curr_access = curr_access.strictest(req_access);
-        metas.push(meta);
+        push_unique_meta(metas, meta);
+        Ok(())
+    }
+
+    pub fn push_transient_access(
+        &mut self,
+        scope: &ScopeId<'a>,
+        component: ComponentId<'a>,
+        req_access: Mutability,
+        meta: BorrowMeta<'a>,
+    ) -> anyhow::Result<()> {
+        let scope_idx = self.get_scope_node(scope)?;
+        if self.is_ignored(&component) {
+            return Ok(());
+        }
+
+        let (curr_access, metas) = self.call_graph[scope_idx]
+            .transient_borrows
+            .entry(component)
+            .or_default();
+
+        *curr_access = curr_access.strictest(req_access);
+        push_unique_meta(metas, meta);
+        Ok(())
     }
 
     pub fn push_grant(
         &mut self,
-        scope: ScopeId<'a>,
+        scope: &ScopeId<'a>,
         component: ComponentId<'a>,
         req_access: Mutability,
         meta: BorrowMeta<'a>,
-    ) {
-        let scope_idx = self.get_scope_node(scope);
+    ) -> anyhow::Result<()> {
+        let scope_idx = self.get_scope_node(scope)?;
+        if self.is_ignored(&component) {
+            return Ok(());
+        }
+
         let (curr_access, metas) = self.call_graph[scope_idx]
             .grants
             .entry(component)
-            .or_insert_with(Default::default);
+            .or_default();
 
         *curr_access = curr_access.strictest(req_access);
-        metas.push(meta);
+        push_unique_meta(metas, meta);
+        Ok(())
+    }
+
+    /// Records a borrow which may be held while the scope's async task is suspended. This is also
+    /// recorded as an ordinary borrow.
+    pub fn push_hold(
+        &mut self,
+        scope: &ScopeId<'a>,
+        component: ComponentId<'a>,
+        req_access: Mutability,
+        meta: BorrowMeta<'a>,
+    ) -> anyhow::Result<()> {
+        self.push_access(scope, component.clone(), req_access, meta)?;
+
+        let scope_idx = self.get_scope_node(scope)?;
+        if self.is_ignored(&component) {
+            return Ok(());
+        }
+
+        let (curr_access, metas) = self.call_graph[scope_idx]
+            .holds
+            .entry(component)
+            .or_default();
+
+        *curr_access = curr_access.strictest(req_access);
+        push_unique_meta(metas, meta);
+        Ok(())
+    }
+
+    /// Records that `scope` is the root of an async task. Every task may run while any other is
+    /// suspended.
+    pub fn push_task_root(&mut self, scope: &ScopeId<'a>) -> anyhow::Result<()> {
+        let scope = self.get_scope_node(scope)?;
+        self.task_roots.insert(scope);
+        Ok(())
+    }
+
+    /// Records that the entry point `name` (e.g. a test) enters `scope`. Diagnostics list the entry
+    /// points from which they may be reached.
+    pub fn push_entry_point(&mut self, scope: &ScopeId<'a>, name: &'a str) -> anyhow::Result<()> {
+        let scope = self.get_scope_node(scope)?;
+        let entry_points = self.entry_points.entry(scope).or_default();
+
+        if !entry_points.contains(&name) {
+            entry_points.push(name);
+        }
+
+        Ok(())
     }
 
-    pub fn annotate_scope(&mut self, scope: ScopeId<'a>, meta: ScopeMeta<'a>) {
-        let scope_idx = self.get_scope_node(scope);
-        self.call_graph[scope_idx].meta = Some(meta);
+    fn is_ignored(&self, component: &ComponentId<'a>) -> bool {
+        self.ignored_components
+            .iter()
+            .any(|ignored| path_matches(&component.0, ignored))
+    }
+
+    /// Lists the configured roots which don't match any scope in the binary.
+    pub fn unmatched_roots(&self) -> Vec<&str> {
+        self.root_names
+            .iter()
+            .filter(|root| {
+                !self
+                    .roots
+                    .iter()
+                    .any(|&idx| path_matches(&self.call_graph[idx].meta.name, root))
+            })
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// The scopes which are checked. If roots were configured, these are the scopes they or any
+    /// async task may call or spawn, since tasks are entered by the executor. Otherwise, every
+    /// scope is checked.
+    fn live_scopes(&self) -> Option<FxHashSet<NodeIndex>> {
+        (!self.root_names.is_empty()).then(|| {
+            self.roots
+                .iter()
+                .chain(&self.task_roots)
+                .flat_map(|&root| self.task_scopes(root, true))
+                .collect()
+        })
     }
 
     pub fn annotate_component(&mut self, component: ComponentId<'a>, meta: ComponentMeta<'a>) {
         self.component_meta.insert(component, meta);
     }
 
-    pub fn validate(&self) -> anyhow::Result<()> {
-        // Assuming our graph is a DAG, toposort the scopes.
-        let Ok(topos) = toposort(&self.call_graph, None) else {
-            // If the graph is not a DAG, we know that it is invalid since a dependency issue could
-            // be induced by taking the same borrowing edge several times.
-            //
-            // We generate a list of offending scopes using "Tarjan's strongly connected components
-            // algorithm." A strongly connected component (or SCC) is a set of nodes in a graph
-            // where each node in the set has a path to another node in that set. We know that
-            // finding the SCCs in a graph is an effective way of finding portions of the graph
-            // containing cycles because:
-            //
-            // 1. If the graph contains a cycle, that cycle will be part of an SCC (although the SCC may
-            //    contain more nodes than just it).
-            // 2. If the graph contains an SCC, within that SCC, we can construct many simple cycles
-            //    by taking any of the paths from any of the nodes to itself.
-            //
-            // Hence, determining SCCs is an effective way of printing out portions of the graph with
-            // offending cycles.
-            //
-            // We decided to list out SCCs rather than simple cycles because, in the worst case scenario,
-            // the number of simple cycles in a graph grows factorially w.r.t the number of vertices.
-            // This is because, in a K^n graph, our cycles would be at least all possible permutations of
-            // those `n` nodes.
-            let sccs = petgraph::algo::tarjan_scc(&self.call_graph);
-            let mut f = String::new();
-            write!(
-                f,
-                "Failed to validate saddle scope graph: scopes may be called in a cycle, which could \
-                 cause borrow violations.\n\
-                 \n\
-                 The following scopes form cycles:\n\n",
-            ).unwrap();
-
-            let mut i = 1;
-
-            for scc in sccs.into_iter() {
-                // If the SCC is just an individual node without any self-edges, ignore it.
-                if scc.len() == 1
-                    && !self
-                        .call_graph
-                        .edges_directed(scc[0], Direction::Outgoing)
-                        .any(|edge| edge.source() == edge.target())
-                {
-                    continue;
-                }
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        self.analyze().0
+    }
 
-                // Otherwise, print out the SCCs nodes and the way they connect to one another.
-                writeln!(f, "Cycle {}:", i).unwrap();
-                i += 1;
+    /// Validates the graph and also returns the IDs of the components which could possibly be
+    /// borrowed when each scope is entered, indexed by node.
+    fn analyze(&self) -> (Vec<Diagnostic>, Vec<FxHashMap<String, Mutability>>) {
+        // Recursive scope structures are fine so long as no component held somewhere in the cycle
+        // is borrowed incompatibly when the cycle is re-entered. Hence, rather than rejecting every
+        // cycle outright, we compute the set of all components which could possibly be borrowed
+        // when each scope is entered as a fixed point and only report cycles alongside the
+        // conflicts they cause.
+        //
+        // To do so, we need to know which scopes are involved in cycles. We determine these using
+        // "Tarjan's strongly connected components algorithm." A strongly connected component (or
+        // SCC) is a set of nodes in a graph where each node in the set has a path to another node
+        // in that set. We know that finding the SCCs in a graph is an effective way of finding
+        // portions of the graph containing cycles because:
+        //
+        // 1. If the graph contains a cycle, that cycle will be part of an SCC (although the SCC may
+        //    contain more nodes than just it).
+        // 2. If the graph contains an SCC, within that SCC, we can construct many simple cycles
+        //    by taking any of the paths from any of the nodes to itself.
+        //
+        // We decided to list out SCCs rather than simple cycles because, in the worst case scenario,
+        // the number of simple cycles in a graph grows factorially w.r.t the number of vertices.
+        // This is because, in a K^n graph, our cycles would be at least all possible permutations of
+        // those `n` nodes.
+        let mut cyclic_sccs = Vec::new();
+        let mut node_to_scc = FxHashMap::default();
+
+        for scc in petgraph::algo::tarjan_scc(&self.call_graph) {
+            // If the SCC is just an individual node without any self-edges, ignore it.
+            if scc.len() == 1
+                && !self
+                    .call_graph
+                    .edges_directed(scc[0], Direction::Outgoing)
+                    .any(|edge| edge.source() == edge.target())
+            {
+                continue;
+            }
 
-                let scc = scc.into_iter().collect::<FxHashSet<_>>();
+            for &scope in &scc {
+                node_to_scc.insert(scope, cyclic_sccs.len());
+            }
 
-                for &scope in &scc {
-                    writeln!(
-                        f,
-                        " - Scope {}, which could call into...",
-                        self.call_graph[scope].meta.as_ref().unwrap().name,
-                    )
-                    .unwrap();
+            cyclic_sccs.push(scc);
+        }
 
-                    for caller in self.call_graph.edges_directed(scope, Direction::Outgoing) {
-                        if !scc.contains(&caller.target()) {
-                            continue;
-                        }
+        // Using a worklist, we populate the set of all components which could possibly be borrowed
+        // when a scope is entered until it reaches a fixed point. This always terminates because
+        // these sets can only ever grow and are bounded by the set of all components.
+        //
+        // Each scope is analyzed once for every calling context it may be entered in, that being the
+        // last `context_depth` calls leading to it. With a depth of zero, every scope has a single
+        // context whose PBS is the union over all of its callers.
+        struct ValidationCx<'a> {
+            validator: &'a Validator<'a>,
+            /// The scope and calling context of each state. Contexts list their most recent call
+            /// last.
+            states: Vec<(NodeIndex, Vec<EdgeIndex>)>,
+            state_ids: FxHashMap<(NodeIndex, Vec<EdgeIndex>), usize>,
+            /// The states of each scope, indexed by node.
+            node_states: Vec<Vec<usize>>,
+            /// The states which may call each state along with the call through which they do so.
+            state_callers: Vec<Vec<(usize, EdgeIndex)>>,
+            /// The PBS of each state.
+            potentially_borrowed: Vec<FxHashMap<ComponentId<'a>, Mutability>>,
+            cyclic_sccs: &'a [Vec<NodeIndex>],
+            node_to_scc: &'a FxHashMap<NodeIndex, usize>,
+            responsibility_dags: FxHashMap<(ComponentId<'a>, Mutability), ResponsibilityDag>,
+            diagnostics: Vec<Diagnostic>,
+        }
+
+        /// The set of callers responsible for each state potentially holding a given component in
+        /// a way incompatible with a given mutability. This is computed once for every component
+        /// and mutability pair involved in a conflict and shared between all of their explanations.
+        struct ResponsibilityDag {
+            callers: FxHashMap<usize, Vec<(usize, Mutability, Vec<String>)>>,
+        }
 
-                        writeln!(
-                            f,
-                            "    - Scope {} through the invocation declared at {}",
-                            self.call_graph[caller.target()].meta.as_ref().unwrap().name,
-                            caller.weight().def_path,
+        impl ResponsibilityDag {
+            pub fn new<'a>(
+                cx: &ValidationCx<'a>,
+                desired_comp: &ComponentId<'a>,
+                desired_mut: Mutability,
+            ) -> Self {
+                let validator = cx.validator;
+                let mut callers = FxHashMap::<_, Vec<(usize, Mutability, Vec<String>)>>::default();
+
+                // A caller is responsible if it may hold the component incompatibly while making
+                // the call, either because it inherited it or because it borrowed it itself, and
+                // the callee's grants of the component do not release it.
+                let held_by = |caller: usize, callee: usize, call: &CallMeta| {
+                    let held = cx.potentially_borrowed[caller]
+                        .get(desired_comp)
+                        .copied()
+                        .into_iter()
+                        .chain(
+                            validator.call_graph[cx.states[caller].0]
+                                .borrows
+                                .get(desired_comp)
+                                .filter(|_| call.holds(desired_comp))
+                                .map(|(m, _)| *m),
                         )
-                        .unwrap();
+                        .reduce(Mutability::strictest)?;
+
+                    let grant = validator.call_graph[cx.states[callee].0]
+                        .grants
+                        .get(desired_comp)
+                        .map(|(m, _)| *m);
+
+                    let held = match grant {
+                        Some(Mutability::Mutable) => return None,
+                        Some(Mutability::Immutable) => Mutability::Immutable,
+                        None => held,
+                    };
+
+                    Some(held).filter(|v| !v.is_compatible_with(desired_mut))
+                };
+
+                for (callee, state_callers) in cx.state_callers.iter().enumerate() {
+                    for &(caller, edge) in state_callers {
+                        let call = &validator.call_graph[edge];
+                        let Some(caller_mut) = held_by(caller, callee, call) else {
+                            continue;
+                        };
+
+                        let call_site = call.def_path.to_string();
+                        let callee_callers = callers.entry(callee).or_default();
+
+                        match callee_callers
+                            .iter_mut()
+                            .find(|(other, _, _)| *other == caller)
+                        {
+                            Some((_, held_mut, call_sites)) => {
+                                *held_mut = held_mut.strictest(caller_mut);
+                                call_sites.push(call_site);
+                            }
+                            None => callee_callers.push((caller, caller_mut, vec![call_site])),
+                        }
                     }
                 }
 
-                writeln!(f).unwrap();
+                Self { callers }
             }
 
-            anyhow::bail!("{f}");
-        };
+            /// Collects the portion of the DAG responsible for `targets`, listing every state once
+            /// regardless of how many paths lead to it. The targets must be states of the same scope
+            /// and are explained together as the first node. Also returns the scopes which hold the
+            /// component without being called by a responsible caller.
+            pub fn explain<'a>(
+                &self,
+                cx: &ValidationCx<'a>,
+                desired_comp: &ComponentId<'a>,
+                targets: &[usize],
+            ) -> (Responsibility, Vec<NodeIndex>) {
+                let mut node_ids = targets
+                    .iter()
+                    .map(|&target| (target, 0))
+                    .collect::<FxHashMap<_, _>>();
+                let mut node_count = 1;
+                let mut queue = VecDeque::from_iter([targets.to_vec()]);
+                let mut nodes = Vec::<ResponsibilityNode>::new();
+                let mut origins = Vec::new();
+
+                while let Some(states) = queue.pop_front() {
+                    let scope = cx.states[states[0]].0;
+
+                    // There are two ways our scope may have been called with a specific offending
+                    // borrow type: inherited and direct. We begin by collecting the direct borrows.
+                    // Transient borrows are only relevant to the scope being explained since they
+                    // are never held while calling out.
+                    let scope_info = &cx.validator.call_graph[scope];
+                    let transient = scope_info
+                        .transient_borrows
+                        .get(desired_comp)
+                        .filter(|_| nodes.is_empty());
+
+                    let borrowed_at = scope_info
+                        .borrows
+                        .get(desired_comp)
+                        .into_iter()
+                        .chain(transient)
+                        .flat_map(|(_, borrow_meta)| borrow_meta)
+                        .map(|borrow_meta| BorrowSite {
+                            mutability: borrow_meta.mutability,
+                            location: borrow_meta.def_path.to_string(),
+                        })
+                        .collect();
+
+                    // Grants only weaken what the scope's callers hold, so they are listed to
+                    // explain why those callers hold the component immutably.
+                    let granted_at = scope_info
+                        .grants
+                        .get(desired_comp)
+                        .into_iter()
+                        .flat_map(|(_, grant_meta)| grant_meta)
+                        .map(|grant_meta| BorrowSite {
+                            mutability: grant_meta.mutability,
+                            location: grant_meta.def_path.to_string(),
+                        })
+                        .collect();
+
+                    // Now, we collect the indirect calls, visiting each caller only once. Callers
+                    // shared by several targets are merged.
+                    let mut callers = Vec::<ResponsibleCaller>::new();
+
+                    for (caller, held_mutability, call_sites) in states
+                        .iter()
+                        .flat_map(|state| self.callers.get(state))
+                        .flatten()
+                    {
+                        let caller = *caller;
+                        let node = *node_ids.entry(caller).or_insert_with(|| {
+                            queue.push_back(vec![caller]);
+                            node_count += 1;
+                            node_count - 1
+                        });
+
+                        match callers.iter_mut().find(|other| other.node == node) {
+                            Some(other) => {
+                                other.held_mutability =
+                                    other.held_mutability.strictest(*held_mutability);
+
+                                for call_site in call_sites {
+                                    if !other.call_sites.contains(call_site) {
+                                        other.call_sites.push(call_site.clone());
+                                    }
+                                }
+                            }
+                            None => callers.push(ResponsibleCaller {
+                                node,
+                                held_mutability: *held_mutability,
+                                call_sites: call_sites.clone(),
+                            }),
+                        }
+                    }
 
-        // Working in topological order, we populate the set of all components which could possibly
-        // be borrowed when a scope is entered.
-        struct ValidationCx<'a> {
-            validator: &'a Validator<'a>,
-            potentially_borrowed: Vec<FxHashMap<ComponentId<'a>, Mutability>>,
-            err_msg_or_empty: String,
+                    if callers.is_empty() && !origins.contains(&scope) {
+                        origins.push(scope);
+                    }
+
+                    nodes.push(ResponsibilityNode {
+                        scope: cx.validator.scope_ref(scope),
+                        borrowed_at,
+                        granted_at,
+                        callers,
+                    });
+                }
+
+                (Responsibility { nodes }, origins)
+            }
         }
 
         impl<'a> ValidationCx<'a> {
-            pub fn new(validator: &'a Validator, node_count: usize) -> Self {
+            pub fn new(
+                validator: &'a Validator,
+                cyclic_sccs: &'a [Vec<NodeIndex>],
+                node_to_scc: &'a FxHashMap<NodeIndex, usize>,
+            ) -> Self {
                 Self {
                     validator,
-                    potentially_borrowed: (0..node_count).map(|_| FxHashMap::default()).collect(),
-                    err_msg_or_empty: String::new(),
+                    states: Vec::new(),
+                    state_ids: FxHashMap::default(),
+                    node_states: vec![Vec::new(); validator.call_graph.node_count()],
+                    state_callers: Vec::new(),
+                    potentially_borrowed: Vec::new(),
+                    cyclic_sccs,
+                    node_to_scc,
+                    responsibility_dags: FxHashMap::default(),
+                    diagnostics: Vec::new(),
                 }
             }
 
-            pub fn validate_scope(&mut self, scope: NodeIndex, scope_info: &Scope) {
-                let f = &mut self.err_msg_or_empty;
-                let pbs = &self.potentially_borrowed[scope.index()];
+            /// Gets the state of `scope` entered in `context`, creating it if it doesn't exist
+            /// yet. Also returns whether the state was created.
+            fn state(&mut self, scope: NodeIndex, context: Vec<EdgeIndex>) -> (usize, bool) {
+                if let Some(&state) = self.state_ids.get(&(scope, context.clone())) {
+                    return (state, false);
+                }
 
-                for (req_ty, (req_mut, _req_meta)) in &scope_info.borrows {
-                    // If the request is compatible with the PBS, ignore it.
-                    let Some(pre_mut) = pbs.get(&req_ty) else {
-                        continue;
-                    };
+                let state = self.states.len();
+                self.states.push((scope, context.clone()));
+                self.state_ids.insert((scope, context), state);
+                self.node_states[scope.index()].push(state);
+                self.state_callers.push(Vec::new());
+                self.potentially_borrowed.push(FxHashMap::default());
 
-                    if pre_mut.is_compatible_with(*req_mut) {
-                        return;
-                    }
+                (state, true)
+            }
 
-                    // Otherwise, log out the error chain.
-                    let comp_meta = &self.validator.component_meta[&req_ty];
-                    writeln!(
-                        f,
-                        "The scope {} defined at {} borrows the component {} {} even though it may have already been borrowed {}.",
-                        scope_info.meta.as_ref().unwrap().name,
-                        scope_info.meta.as_ref().unwrap().defined_at,
-                        comp_meta.name,
-                        req_mut.adjective(),
-                        pre_mut.adjective(),
-                    )
-                    .unwrap();
-
-                    // TODO: Improve diagnostics for sub grants
-                    fn print_tree<'a>(
-                        f: &mut String,
-                        validator: &Validator,
-                        potentially_borrowed: &[FxHashMap<ComponentId<'a>, Mutability>],
-                        desired_comp: &ComponentId<'a>,
-                        desired_mut: Mutability,
-                        target: NodeIndex,
-                        indent: u32,
-                    ) {
-                        // There are two ways our target node may have been called with a specific
-                        // offending borrow type: inherited and direct.
-
-                        // We begin by logging out the direct calls.
-                        for borrow_meta in validator.call_graph[target]
-                            .borrows
-                            .get(&desired_comp)
-                            .map_or(&Vec::new(), |(_, borrow_meta)| borrow_meta)
-                        {
-                            writeln!(
-                                f,
-                                "{}- This scope could have borrowed the component {} at location {}.",
-                                Indent(indent),
-                                borrow_meta.mutability.adjective(),
-                                borrow_meta.def_path,
-                            )
-                            .unwrap();
+            /// Enters each of `scopes` without any calling context and propagates their borrows
+            /// until a fixed point is reached.
+            pub fn propagate_from(&mut self, scopes: impl IntoIterator<Item = NodeIndex>) {
+                let mut worklist = scopes
+                    .into_iter()
+                    .map(|scope| self.state(scope, Vec::new()).0)
+                    .collect::<VecDeque<_>>();
+                let mut in_worklist = FxHashSet::from_iter(worklist.iter().copied());
+
+                while let Some(state) = worklist.pop_front() {
+                    in_worklist.remove(&state);
+
+                    // Propagate our PBS and our own borrows to others, revisiting them if they
+                    // changed.
+                    let scope = self.states[state].0;
+                    for edge in self
+                        .validator
+                        .call_graph
+                        .edges_directed(scope, Direction::Outgoing)
+                    {
+                        let (callee, changed) = self.propagate_borrows_to_others(state, edge);
+
+                        if changed && in_worklist.insert(callee) {
+                            worklist.push_back(callee);
                         }
+                    }
+                }
+            }
 
-                        // Now, we log out indirect calls.
-                        let mut printed_callers = FxHashSet::default();
+            /// Checks every state of `scope` against its PBS. Conflicts occurring in several
+            /// calling contexts are reported once, listing each of those contexts.
+            pub fn validate_scope(&mut self, scope: NodeIndex) {
+                let scope_info = &self.validator.call_graph[scope];
 
-                        for caller in validator
-                            .call_graph
-                            .neighbors_directed(target, Direction::Incoming)
-                        {
-                            if !printed_callers.insert(caller) {
-                                continue;
-                            }
+                // Transient borrows are checked just like the borrows held for the entire scope.
+                let mut requests = FxHashMap::<_, Mutability>::default();
+                for (req_ty, (req_mut, _req_meta)) in scope_info.all_borrows() {
+                    let curr_mut = requests.entry(req_ty).or_insert(*req_mut);
+                    *curr_mut = curr_mut.strictest(*req_mut);
+                }
 
-                            let Some(caller_mut) = potentially_borrowed[caller.index()]
-                                .get(&desired_comp)
-                                .filter(|v| !v.is_compatible_with(desired_mut))
-                            else {
-                                continue;
-                            };
-
-                            writeln!(
-                                f,
-                                "{}- The scope {} defined at {} may have called it while the component was held {}.\n\
-                                 {}  Hint: the following scopes may have been responsible for the aforementioned call...",
-                                Indent(indent),
-                                validator.call_graph[caller].meta.as_ref().unwrap().name,
-                                validator.call_graph[caller].meta.as_ref().unwrap().defined_at,
-                                caller_mut.adjective(),
-                                Indent(indent),
-                            )
-                            .unwrap();
-
-                            for edge in validator.call_graph.edges_connecting(caller, target) {
-                                writeln!(
-                                    f,
-                                    "{}- {}",
-                                    Indent(indent + INDENT_SIZE),
-                                    edge.weight().def_path,
-                                )
-                                .unwrap();
-                            }
+                for (req_ty, req_mut) in &requests {
+                    let req_ty = *req_ty;
 
-                            writeln!(f, "{}  Tracing back responsibility...", Indent(indent))
-                                .unwrap();
-
-                            print_tree(
-                                f,
-                                validator,
-                                potentially_borrowed,
-                                desired_comp,
-                                desired_mut,
-                                caller,
-                                indent + INDENT_SIZE,
-                            );
+                    // Collect the states whose PBS is incompatible with the request, along with the
+                    // strictest way in which they may hold the component.
+                    let mut held_mut = None::<Mutability>;
+                    let mut states = Vec::new();
+
+                    for &state in &self.node_states[scope.index()] {
+                        let Some(pre_mut) = self.potentially_borrowed[state].get(req_ty) else {
+                            continue;
+                        };
+
+                        if pre_mut.is_compatible_with(*req_mut) {
+                            continue;
                         }
+
+                        held_mut = Some(held_mut.map_or(*pre_mut, |held| held.strictest(*pre_mut)));
+                        states.push(state);
                     }
 
-                    print_tree(
-                        f,
-                        self.validator,
-                        &self.potentially_borrowed,
-                        req_ty,
-                        *req_mut,
-                        scope,
-                        INDENT_SIZE,
-                    );
+                    let Some(held_mut) = held_mut else {
+                        continue;
+                    };
+
+                    // Otherwise, explain the conflict.
+                    if !self
+                        .responsibility_dags
+                        .contains_key(&(req_ty.clone(), *req_mut))
+                    {
+                        let dag = ResponsibilityDag::new(self, req_ty, *req_mut);
+                        self.responsibility_dags
+                            .insert((req_ty.clone(), *req_mut), dag);
+                    }
+
+                    let (explanation, origins) = self.responsibility_dags
+                        [&(req_ty.clone(), *req_mut)]
+                        .explain(self, req_ty, &states);
+
+                    // If the component is held somewhere in a cycle containing this scope, the
+                    // conflict may be caused by re-entering that cycle.
+                    let cycle = self
+                        .node_to_scc
+                        .get(&scope)
+                        .map(|&scc_idx| &self.cyclic_sccs[scc_idx])
+                        .filter(|scc| {
+                            scc.iter().any(|&member| {
+                                self.validator.call_graph[member]
+                                    .borrows
+                                    .contains_key(req_ty)
+                            })
+                        })
+                        .map(|scc| self.validator.cycle_members(scc));
+
+                    let kind = if cycle.is_some() {
+                        ConflictKind::ReentrantBorrow
+                    } else {
+                        ConflictKind::BorrowConflict
+                    };
+
+                    let severity = self.validator.lints.severity_of(kind);
+                    if severity == Severity::Allow {
+                        continue;
+                    }
 
-                    f.push_str("\n\n");
+                    let contexts = if self.validator.context_depth > 0 {
+                        states
+                            .iter()
+                            .map(|&state| self.calling_context(&self.states[state].1))
+                            .collect()
+                    } else {
+                        Vec::new()
+                    };
+
+                    self.diagnostics.push(Diagnostic {
+                        kind,
+                        severity,
+                        scope: self.validator.scope_ref(scope),
+                        component: self.validator.component_meta[req_ty].name.clone(),
+                        mutability: *req_mut,
+                        held_mutability: held_mut,
+                        explanation,
+                        cycle,
+                        contexts,
+                        concurrency: None,
+                        reached_from: self.validator.entry_points_reaching(&origins),
+                    });
                 }
             }
 
-            pub fn propagate_borrows_to_self(&mut self, src_idx: NodeIndex, src: &'a Scope) {
-                // Propagate scope borrows to self
-                // TODO: This is fine to run several times but really shouldn't be.
-                for (req_ty, (req_mut, _req_meta)) in &src.borrows {
-                    let pbs_mut = self.potentially_borrowed[src_idx.index()]
-                        .entry(req_ty.clone())
-                        .or_insert(Mutability::Immutable);
-
-                    *pbs_mut = pbs_mut.strictest(*req_mut);
+            /// Describes the calls forming a calling context. Earlier calls may have been forgotten
+            /// if the context is as long as the context depth and its outermost caller can itself
+            /// be called.
+            fn calling_context(&self, context: &[EdgeIndex]) -> CallingContext {
+                let call_graph = &self.validator.call_graph;
+                let calls = context
+                    .iter()
+                    .map(|&edge| call_graph.edge_endpoints(edge).unwrap())
+                    .collect::<Vec<_>>();
+
+                let truncated = context.len() == self.validator.context_depth
+                    && calls.first().is_some_and(|&(caller, _)| {
+                        self.node_states[caller.index()]
+                            .iter()
+                            .any(|&state| !self.states[state].1.is_empty())
+                    });
+
+                CallingContext {
+                    calls: calls
+                        .iter()
+                        .zip(context)
+                        .map(|(&(caller, callee), &edge)| ContextCall {
+                            caller: self.validator.scope_ref(caller),
+                            callee: self.validator.scope_ref(callee),
+                            call_site: call_graph[edge].def_path.to_string(),
+                        })
+                        .collect(),
+                    truncated,
                 }
             }
 
-            pub fn propagate_borrows_to_others(&mut self, caller: NodeIndex, callee: NodeIndex) {
-                let (caller_pbs, callee_pbs) = borrow_two(
-                    &mut self.potentially_borrowed,
-                    caller.index(),
-                    callee.index(),
-                );
+            /// Propagates the components held by the `caller` state while making `call` to the
+            /// state it enters. Returns that state and whether its PBS changed or it is new.
+            pub fn propagate_borrows_to_others(
+                &mut self,
+                caller: usize,
+                call: EdgeReference<'_, CallMeta<'a>>,
+            ) -> (usize, bool) {
+                let (caller_scope, caller_context) = &self.states[caller];
+                let caller_scope = *caller_scope;
+
+                // The callee's context is ours followed by the call, forgetting the oldest calls
+                // beyond the configured depth.
+                let mut context = caller_context.clone();
+                context.push(call.id());
+                if context.len() > self.validator.context_depth {
+                    context.remove(0);
+                }
+
+                let (callee, created) = self.state(call.target(), context);
+                if !self.state_callers[callee].contains(&(caller, call.id())) {
+                    self.state_callers[callee].push((caller, call.id()));
+                }
+
+                // The components held while the caller makes the call are those which could have
+                // been borrowed on entry plus those the caller borrows itself and holds during it.
+                let mut held = self.potentially_borrowed[caller].clone();
 
-                for (req_ty, req_mut) in &*caller_pbs {
-                    let mut req_mut = *req_mut;
+                for (req_ty, (req_mut, _req_meta)) in
+                    &self.validator.call_graph[caller_scope].borrows
+                {
+                    if !call.weight().holds(req_ty) {
+                        continue;
+                    }
+
+                    let held_mut = held.entry(req_ty.clone()).or_insert(Mutability::Immutable);
+                    *held_mut = held_mut.strictest(*req_mut);
+                }
+
+                let callee_scope = &self.validator.call_graph[call.target()];
+                let callee_pbs = &mut self.potentially_borrowed[callee];
+                let mut changed = created;
 
+                for (req_ty, mut req_mut) in held {
                     // Downgrade callee's PBS if they have a grant for the specific component.
-                    match self.validator.call_graph[callee]
-                        .grants
-                        .get(&req_ty)
-                        .map(|(m, _)| *m)
-                    {
+                    match callee_scope.grants.get(&req_ty).map(|(m, _)| *m) {
                         Some(Mutability::Immutable) => req_mut = Mutability::Immutable,
                         Some(Mutability::Mutable) => continue,
                         None => {}
                     }
 
                     // Extend the callee's PBS.
-                    let pbs_mut = callee_pbs
-                        .entry(req_ty.clone())
-                        .or_insert(Mutability::Immutable);
+                    match callee_pbs.get_mut(&req_ty) {
+                        Some(pbs_mut) => {
+                            let new_mut = pbs_mut.strictest(req_mut);
+                            changed |= new_mut != *pbs_mut;
+                            *pbs_mut = new_mut;
+                        }
+                        None => {
+                            callee_pbs.insert(req_ty, req_mut);
+                            changed = true;
+                        }
+                    }
+                }
 
-                    *pbs_mut = pbs_mut.strictest(req_mut);
+                (callee, changed)
+            }
+        }
+
+        let mut cx = ValidationCx::new(self, &cyclic_sccs, &node_to_scc);
+
+        // Scopes unreachable from the configured roots neither propagate their borrows nor get
+        // checked.
+        let live = self.live_scopes();
+        let is_live = |idx: NodeIndex| live.as_ref().is_none_or(|live| live.contains(&idx));
+        let live_nodes = self
+            .call_graph
+            .node_indices()
+            .filter(|&idx| is_live(idx))
+            .collect::<Vec<_>>();
+
+        // Without calling contexts, every scope is simply entered once. Otherwise, we start from
+        // the scopes without any live callers and then from those only called within cycles which
+        // are unreachable from them.
+        if self.context_depth == 0 {
+            cx.propagate_from(live_nodes.iter().copied());
+        } else {
+            cx.propagate_from(live_nodes.iter().copied().filter(|&idx| {
+                self.call_graph
+                    .neighbors_directed(idx, Direction::Incoming)
+                    .all(|caller| caller == idx || !is_live(caller))
+            }));
+
+            for &idx in &live_nodes {
+                if cx.node_states[idx.index()].is_empty() {
+                    cx.propagate_from([idx]);
                 }
             }
         }
 
-        let mut cx = ValidationCx::new(self, self.call_graph.node_count());
+        // Validate every scope given the final PBS of each of its states.
+        for idx in self.call_graph.node_indices() {
+            cx.validate_scope(idx);
+        }
+
+        // Check the scopes which may run at the same time against one another.
+        let mut diagnostics = std::mem::take(&mut cx.diagnostics);
+        diagnostics.extend(self.concurrent_conflicts(is_live));
+        diagnostics.extend(self.suspended_conflicts(is_live));
+
+        // Report conflicts in a stable order regardless of the order of declarations in the
+        // binary.
+        diagnostics.sort_by(|a, b| {
+            (&a.scope.name, &a.scope.id, &a.component).cmp(&(
+                &b.scope.name,
+                &b.scope.id,
+                &b.component,
+            ))
+        });
+
+        // Components are identified by name alone, so the sets can outlive the validation context.
+        // The PBS of a scope is the union of those of its states.
+        let potentially_borrowed = cx
+            .node_states
+            .iter()
+            .map(|states| {
+                let mut pbs = FxHashMap::<String, Mutability>::default();
+
+                for &state in states {
+                    for (id, mutability) in &cx.potentially_borrowed[state] {
+                        let held = pbs.entry(id.0.clone()).or_insert(*mutability);
+                        *held = held.strictest(*mutability);
+                    }
+                }
+
+                pbs
+            })
+            .collect();
+
+        (diagnostics, potentially_borrowed)
+    }
+
+    /// Keys every scope of the binary the way baselines and diffs refer to them.
+    pub fn scope_keys(&self) -> ScopeKeys {
+        let scopes = self
+            .call_graph
+            .node_indices()
+            .map(|idx| self.scope_ref(idx))
+            .collect::<Vec<_>>();
+
+        ScopeKeys::new(&scopes)
+    }
+
+    fn scope_ref(&self, scope: NodeIndex) -> ScopeRef {
+        let scope = &self.call_graph[scope];
+
+        ScopeRef {
+            id: scope.id.clone(),
+            name: scope.meta.name.clone(),
+            defined_at: scope.meta.defined_at.to_string(),
+        }
+    }
+
+    /// Lists the entry points which may enter any of `scopes`, directly or through their callers.
+    fn entry_points_reaching(&self, scopes: &[NodeIndex]) -> Vec<String> {
+        if self.entry_points.is_empty() {
+            return Vec::new();
+        }
+
+        let mut reaching = scopes
+            .iter()
+            .flat_map(|&scope| self.reachable(scope, Direction::Incoming))
+            .flat_map(|scope| self.entry_points.get(&scope).into_iter().flatten())
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+
+        reaching.sort();
+        reaching.dedup();
+        reaching
+    }
+
+    /// Lists the scopes which are defined in the binary but never referenced by any declaration,
+    /// typically because they are never entered.
+    pub fn unreached_definitions(&self) -> Vec<UnreachedScope> {
+        let reached = self
+            .call_graph
+            .node_weights()
+            .map(|scope| scope.meta.defined_at)
+            .collect::<FxHashSet<_>>();
+
+        let mut unreached = self
+            .definitions
+            .iter()
+            .filter(|(defined_at, _)| !reached.contains(*defined_at))
+            .map(|(defined_at, name)| UnreachedScope {
+                name: name.to_string(),
+                defined_at: defined_at.to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        unreached.sort_by(|a, b| a.defined_at.cmp(&b.defined_at));
+        unreached
+    }
+
+    /// Lists the scopes which were registered with a location matching no definition in the
+    /// binary, e.g. because they were declared by a crate built against an older version of
+    /// saddle. Scopes whose location is unknown cannot be matched and are not listed.
+    pub fn undefined_scopes(&self) -> Vec<ScopeRef> {
+        if self.definitions.is_empty() {
+            return Vec::new();
+        }
+
+        let mut undefined = self
+            .call_graph
+            .node_indices()
+            .filter(|&idx| {
+                let defined_at = self.call_graph[idx].meta.defined_at;
+                defined_at != UNKNOWN_LOCATION && !self.definitions.contains_key(defined_at)
+            })
+            .map(|idx| self.scope_ref(idx))
+            .collect::<Vec<_>>();
+
+        undefined.sort_by(|a, b| (&a.defined_at, &a.name).cmp(&(&b.defined_at, &b.name)));
+        undefined
+    }
 
-        for src_idx in topos {
-            let src = &self.call_graph[src_idx];
+    fn cycle_members(&self, scc: &[NodeIndex]) -> Vec<CycleMember> {
+        let scc_set = scc.iter().copied().collect::<FxHashSet<_>>();
+
+        scc.iter()
+            .map(|&scope| CycleMember {
+                scope: self.scope_ref(scope),
+                calls: self
+                    .call_graph
+                    .edges_directed(scope, Direction::Outgoing)
+                    .filter(|edge| scc_set.contains(&edge.target()))
+                    .map(|edge| CycleCall {
+                        scope: self.scope_ref(edge.target()),
+                        call_site: edge.weight().def_path.to_string(),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
 
-            // Validate ourselves given our PBS
-            cx.validate_scope(src_idx, src);
+// === Concurrency === //
 
-            // Extend our own PBS with our borrows
-            cx.propagate_borrows_to_self(src_idx, src);
+/// The components borrowed by the scopes of a task, along with the strictest way in which each of
+/// them is borrowed and the scopes borrowing it.
+type TaskAccesses<'v, 'a> = FxHashMap<&'v ComponentId<'a>, (Mutability, Vec<NodeIndex>)>;
 
-            // Propagate it to others
-            for callee in self.call_graph.edges_directed(src_idx, Direction::Outgoing) {
-                cx.propagate_borrows_to_others(src_idx, callee.target());
+impl<'a> Validator<'a> {
+    /// The scopes which may run as part of the task started by entering `start`, which are those it
+    /// may transitively call or spawn. If `follow_own_spawns` is false, the scopes spawned directly
+    /// by `start` are not followed so that only the rest of its work is included.
+    fn task_scopes(&self, start: NodeIndex, follow_own_spawns: bool) -> FxHashSet<NodeIndex> {
+        let mut visited = FxHashSet::from_iter([start]);
+        let mut stack = vec![start];
+
+        while let Some(idx) = stack.pop() {
+            let spawned = self
+                .spawns
+                .get(&idx)
+                .filter(|_| follow_own_spawns || idx != start)
+                .into_iter()
+                .flatten()
+                .map(|&(spawned, _)| spawned);
+
+            for next in self
+                .call_graph
+                .neighbors_directed(idx, Direction::Outgoing)
+                .chain(spawned)
+            {
+                if visited.insert(next) {
+                    stack.push(next);
+                }
             }
         }
 
-        // If we had any errors while validating this graph
-        if !cx.err_msg_or_empty.is_empty() {
-            anyhow::bail!(
-                "Failed to validate the scope graph:\n\n{}",
-                cx.err_msg_or_empty,
-            );
+        visited
+    }
+
+    fn task_accesses(&self, scopes: &FxHashSet<NodeIndex>) -> TaskAccesses<'_, 'a> {
+        let mut accesses = TaskAccesses::default();
+
+        for &scope in scopes {
+            for (component, (mutability, _)) in self.call_graph[scope].all_borrows() {
+                let (strictest, borrowers) = accesses
+                    .entry(component)
+                    .or_insert((*mutability, Vec::new()));
+
+                *strictest = strictest.strictest(*mutability);
+                if !borrowers.contains(&scope) {
+                    borrowers.push(scope);
+                }
+            }
         }
 
-        // Otherwise, the graph is fully valid.
-        Ok(())
+        accesses
+    }
+
+    /// Finds the components borrowed incompatibly by tasks which may run at the same time. A
+    /// spawned scope, along with everything it calls or spawns, may run at the same time as the
+    /// rest of its spawner and as every other scope spawned by it, including itself if it is
+    /// spawned at more than one site.
+    fn concurrent_conflicts(&self, is_live: impl Fn(NodeIndex) -> bool) -> Vec<Diagnostic> {
+        let severity = self.lints.severity_of(ConflictKind::ConcurrentAccess);
+        if severity == Severity::Allow {
+            return Vec::new();
+        }
+
+        let mut diagnostics = Vec::new();
+
+        for (&spawner, spawns) in &self.spawns {
+            if !is_live(spawner) {
+                continue;
+            }
+
+            // Group the spawn sites by the scope they spawn.
+            let mut spawned = Vec::<(NodeIndex, Vec<&str>)>::new();
+
+            for &(scope, meta) in spawns {
+                match spawned.iter_mut().find(|(other, _)| *other == scope) {
+                    Some((_, sites)) => sites.push(meta.def_path),
+                    None => spawned.push((scope, vec![meta.def_path])),
+                }
+            }
+
+            let spawn_sites = |scope: NodeIndex, sites: &[&str]| {
+                sites
+                    .iter()
+                    .map(|site| SpawnSite {
+                        spawner: self.scope_ref(spawner),
+                        spawned: self.scope_ref(scope),
+                        location: site.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+            };
+
+            let rest = self.task_accesses(&self.task_scopes(spawner, false));
+            let tasks = spawned
+                .iter()
+                .map(|&(scope, _)| self.task_accesses(&self.task_scopes(scope, true)))
+                .collect::<Vec<_>>();
+
+            for (i, (scope, sites)) in spawned.iter().enumerate() {
+                let task = (*scope, &tasks[i]);
+                let spawned_at = spawn_sites(*scope, sites);
+
+                self.push_concurrent_conflicts(
+                    &mut diagnostics,
+                    severity,
+                    spawner,
+                    task,
+                    (spawner, &rest),
+                    &spawned_at,
+                );
+
+                if sites.len() > 1 {
+                    self.push_concurrent_conflicts(
+                        &mut diagnostics,
+                        severity,
+                        spawner,
+                        task,
+                        task,
+                        &spawned_at,
+                    );
+                }
+
+                for (j, (other, other_sites)) in spawned.iter().enumerate().skip(i + 1) {
+                    let mut spawned_at = spawned_at.clone();
+                    spawned_at.extend(spawn_sites(*other, other_sites));
+
+                    self.push_concurrent_conflicts(
+                        &mut diagnostics,
+                        severity,
+                        spawner,
+                        task,
+                        (*other, &tasks[j]),
+                        &spawned_at,
+                    );
+                }
+            }
+        }
+
+        diagnostics
+    }
+
+    fn push_concurrent_conflicts(
+        &self,
+        diagnostics: &mut Vec<Diagnostic>,
+        severity: Severity,
+        spawner: NodeIndex,
+        (scope, accesses): (NodeIndex, &TaskAccesses<'_, 'a>),
+        (other, other_accesses): (NodeIndex, &TaskAccesses<'_, 'a>),
+        spawned_at: &[SpawnSite],
+    ) {
+        for (component, (mutability, borrowers)) in accesses {
+            let Some((other_mutability, other_borrowers)) = other_accesses.get(component) else {
+                continue;
+            };
+
+            if mutability.is_compatible_with(*other_mutability) {
+                continue;
+            }
+
+            diagnostics.push(Diagnostic {
+                kind: ConflictKind::ConcurrentAccess,
+                severity,
+                scope: self.scope_ref(scope),
+                component: self.component_meta[*component].name.clone(),
+                mutability: *mutability,
+                held_mutability: *other_mutability,
+                explanation: Responsibility::default(),
+                cycle: None,
+                contexts: Vec::new(),
+                concurrency: Some(Concurrency {
+                    other: self.scope_ref(other),
+                    spawned_at: spawned_at.to_vec(),
+                    suspended_task: None,
+                    accesses: self.task_access_list(component, borrowers),
+                    other_accesses: self.task_access_list(component, other_borrowers),
+                }),
+                reached_from: self.entry_points_reaching(&[spawner]),
+            });
+        }
+    }
+
+    /// Finds the components held by async tasks while they are suspended which may be borrowed
+    /// incompatibly by any other task running in the meantime.
+    fn suspended_conflicts(&self, is_live: impl Fn(NodeIndex) -> bool) -> Vec<Diagnostic> {
+        let severity = self.lints.severity_of(ConflictKind::SuspendedBorrow);
+        if severity == Severity::Allow {
+            return Vec::new();
+        }
+
+        let mut roots = self
+            .task_roots
+            .iter()
+            .copied()
+            .filter(|&root| is_live(root))
+            .collect::<Vec<_>>();
+        roots.sort();
+
+        let tasks = roots
+            .iter()
+            .map(|&root| self.task_scopes(root, true))
+            .collect::<Vec<_>>();
+
+        let accesses = tasks
+            .iter()
+            .map(|task| self.task_accesses(task))
+            .collect::<Vec<_>>();
+
+        // A scope shared between several tasks is only reported once against each other task.
+        let mut reported = FxHashSet::default();
+        let mut diagnostics = Vec::new();
+
+        for (i, &root) in roots.iter().enumerate() {
+            let mut suspending = tasks[i]
+                .iter()
+                .copied()
+                .filter(|&scope| !self.call_graph[scope].holds.is_empty())
+                .collect::<Vec<_>>();
+            suspending.sort();
+
+            for scope in suspending {
+                for (j, &other) in roots.iter().enumerate() {
+                    if i == j || !reported.insert((scope, other)) {
+                        continue;
+                    }
+
+                    for (component, (held, metas)) in &self.call_graph[scope].holds {
+                        let Some((other_mutability, other_borrowers)) = accesses[j].get(component)
+                        else {
+                            continue;
+                        };
+
+                        if held.is_compatible_with(*other_mutability) {
+                            continue;
+                        }
+
+                        diagnostics.push(Diagnostic {
+                            kind: ConflictKind::SuspendedBorrow,
+                            severity,
+                            scope: self.scope_ref(scope),
+                            component: self.component_meta[component].name.clone(),
+                            mutability: *held,
+                            held_mutability: *other_mutability,
+                            explanation: Responsibility::default(),
+                            cycle: None,
+                            contexts: Vec::new(),
+                            concurrency: Some(Concurrency {
+                                other: self.scope_ref(other),
+                                spawned_at: Vec::new(),
+                                suspended_task: Some(self.scope_ref(root)),
+                                accesses: vec![TaskAccess {
+                                    scope: self.scope_ref(scope),
+                                    borrowed_at: metas
+                                        .iter()
+                                        .map(|meta| BorrowSite {
+                                            mutability: meta.mutability,
+                                            location: meta.def_path.to_string(),
+                                        })
+                                        .collect(),
+                                }],
+                                other_accesses: self.task_access_list(component, other_borrowers),
+                            }),
+                            reached_from: self.entry_points_reaching(&[root]),
+                        });
+                    }
+                }
+            }
+        }
+
+        diagnostics
+    }
+
+    fn task_access_list(
+        &self,
+        component: &ComponentId<'a>,
+        borrowers: &[NodeIndex],
+    ) -> Vec<TaskAccess> {
+        let mut list = borrowers
+            .iter()
+            .map(|&scope| TaskAccess {
+                scope: self.scope_ref(scope),
+                borrowed_at: self.call_graph[scope]
+                    .all_borrows()
+                    .filter(|(id, _)| *id == component)
+                    .flat_map(|(_, (_, metas))| metas)
+                    .map(|meta| BorrowSite {
+                        mutability: meta.mutability,
+                        location: meta.def_path.to_string(),
+                    })
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+
+        list.sort_by(|a, b| a.scope.name.cmp(&b.scope.name));
+        list
+    }
+}
+
+// === Graph Export === //
+
+/// Restricts the portion of the call graph produced by [`Validator::export_graph`].
+#[derive(Debug, Clone, Default)]
+pub struct GraphFilter<'q> {
+    /// Only include scopes reachable from this scope.
+    pub reachable_from: Option<&'q str>,
+    /// Only include scopes from which this scope is reachable.
+    pub reaching: Option<&'q str>,
+    /// Only include scopes which borrow or grant this component.
+    pub component: Option<&'q str>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScopeGraph {
+    pub nodes: Vec<ScopeGraphNode>,
+    pub edges: Vec<ScopeGraphEdge>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScopeGraphNode {
+    pub scope: ScopeRef,
+    pub borrows: Vec<(String, Mutability)>,
+    pub transient_borrows: Vec<(String, Mutability)>,
+    pub grants: Vec<(String, Mutability)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScopeGraphEdge {
+    /// The index of the calling scope in [`ScopeGraph::nodes`].
+    pub from: usize,
+    /// The index of the called scope in [`ScopeGraph::nodes`].
+    pub to: usize,
+    pub call_site: String,
+    /// The components of the caller held during the call if it declared them.
+    pub held: Option<Vec<String>>,
+}
+
+impl Validator<'_> {
+    /// Finds the scope whose name is `query` or ends with the path segments in `query`.
+    fn find_scope(&self, query: &str) -> anyhow::Result<NodeIndex> {
+        let matches = self
+            .call_graph
+            .node_indices()
+            .filter(|&idx| path_matches(&self.call_graph[idx].meta.name, query))
+            .collect::<Vec<_>>();
+
+        match matches.as_slice() {
+            [] => anyhow::bail!("No scope named {query} was found in the binary."),
+            [idx] => Ok(*idx),
+            _ => {
+                let mut names = matches
+                    .iter()
+                    .map(|&idx| self.scope_ref(idx).name)
+                    .collect::<Vec<_>>();
+                names.sort();
+
+                anyhow::bail!(
+                    "The scope name {query} is ambiguous. It could refer to any of: {}",
+                    names.join(", "),
+                );
+            }
+        }
+    }
+
+    fn find_component(&self, query: &str) -> anyhow::Result<&ComponentId<'_>> {
+        self.component_meta
+            .iter()
+            .find(|(id, meta)| id.0 == query || meta.name == query)
+            .map(|(id, _)| id)
+            .with_context(|| format!("No component named {query} was found in the binary."))
+    }
+
+    fn reachable(&self, start: NodeIndex, dir: Direction) -> FxHashSet<NodeIndex> {
+        let mut visited = FxHashSet::from_iter([start]);
+        let mut stack = vec![start];
+
+        while let Some(idx) = stack.pop() {
+            for next in self.call_graph.neighbors_directed(idx, dir) {
+                if visited.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+
+        visited
+    }
+
+    pub fn export_graph(&self, filter: &GraphFilter) -> anyhow::Result<ScopeGraph> {
+        let reachable_from = filter
+            .reachable_from
+            .map(|query| anyhow::Ok(self.reachable(self.find_scope(query)?, Direction::Outgoing)))
+            .transpose()?;
+
+        let reaching = filter
+            .reaching
+            .map(|query| anyhow::Ok(self.reachable(self.find_scope(query)?, Direction::Incoming)))
+            .transpose()?;
+
+        let component = filter
+            .component
+            .map(|query| self.find_component(query))
+            .transpose()?;
+
+        // Determine the set of nodes to include. Node indices follow the order in which scopes were
+        // registered, which depends on the layout of the binary, so the nodes are sorted to keep
+        // the output stable.
+        let mut node_map = FxHashMap::default();
+        let mut nodes = Vec::new();
+
+        let mut indices = self
+            .call_graph
+            .node_indices()
+            .map(|idx| (self.scope_ref(idx), idx))
+            .collect::<Vec<_>>();
+
+        indices.sort_by(|(a, _), (b, _)| {
+            (&a.name, &a.defined_at, &a.id).cmp(&(&b.name, &b.defined_at, &b.id))
+        });
+
+        for (scope_ref, idx) in indices {
+            let scope = &self.call_graph[idx];
+
+            if reachable_from
+                .as_ref()
+                .is_some_and(|set| !set.contains(&idx))
+                || reaching.as_ref().is_some_and(|set| !set.contains(&idx))
+                || component.is_some_and(|comp| {
+                    !scope.borrows.contains_key(comp)
+                        && !scope.transient_borrows.contains_key(comp)
+                        && !scope.grants.contains_key(comp)
+                })
+            {
+                continue;
+            }
+
+            let list = |map: &FxHashMap<ComponentId<'_>, (Mutability, Vec<BorrowMeta<'_>>)>| {
+                let mut list = map
+                    .iter()
+                    .map(|(id, (mutability, _))| {
+                        let name = self
+                            .component_meta
+                            .get(id)
+                            .map_or_else(|| id.0.clone(), |meta| meta.name.clone());
+                        (name, *mutability)
+                    })
+                    .collect::<Vec<_>>();
+                list.sort();
+                list
+            };
+
+            node_map.insert(idx, nodes.len());
+            nodes.push(ScopeGraphNode {
+                scope: scope_ref,
+                borrows: list(&scope.borrows),
+                transient_borrows: list(&scope.transient_borrows),
+                grants: list(&scope.grants),
+            });
+        }
+
+        // Collect the edges between them.
+        let mut edges = Vec::new();
+
+        for edge in self.call_graph.edge_references() {
+            let (Some(&from), Some(&to)) =
+                (node_map.get(&edge.source()), node_map.get(&edge.target()))
+            else {
+                continue;
+            };
+
+            edges.push(ScopeGraphEdge {
+                from,
+                to,
+                call_site: edge.weight().def_path.to_string(),
+                held: edge.weight().held.map(<[String]>::to_vec),
+            });
+        }
+
+        edges.sort_by(|a, b| {
+            (a.from, a.to, &a.call_site, &a.held).cmp(&(b.from, b.to, &b.call_site, &b.held))
+        });
+
+        Ok(ScopeGraph { nodes, edges })
+    }
+}
+
+// === Why Queries === //
+
+/// A call chain along which a component may be held when the chain's last scope is entered.
+#[derive(Debug, Clone)]
+pub struct WhyChain {
+    pub steps: Vec<WhyStep>,
+    /// The way in which the component is held when the last scope is entered.
+    pub held: Mutability,
+}
+
+#[derive(Debug, Clone)]
+pub struct WhyStep {
+    pub scope: ScopeRef,
+    /// The call sites through which the previous step may call this scope. This is empty for the
+    /// first step.
+    pub call_sites: Vec<String>,
+    /// The grant of the component by this scope if it affected a held component, along with where
+    /// it was declared.
+    pub grant: Option<(Mutability, Vec<String>)>,
+    /// The borrow of the component by this scope, if any, along with where it was declared.
+    pub borrow: Option<(Mutability, Vec<String>)>,
+    /// The way in which the component is held once this scope has been entered.
+    pub held_on_entry: Option<Mutability>,
+}
+
+impl Validator<'_> {
+    /// Finds up to `count` of the shortest call chains starting at a root scope along which
+    /// `component` may be held when `scope` is entered.
+    pub fn why(&self, scope: &str, component: &str, count: usize) -> anyhow::Result<Vec<WhyChain>> {
+        let target = self.find_scope(scope)?;
+        let component = self.find_component(component)?;
+
+        // The way in which the component is held by a scope entered in state `held` while it makes
+        // `call` and the way in which it is held once the callee applies its grants.
+        let held_while_calling = |scope: NodeIndex, call: &CallMeta, held: Option<Mutability>| {
+            let borrow = self.call_graph[scope]
+                .borrows
+                .get(component)
+                .filter(|_| call.holds(component))
+                .map(|(m, _)| *m);
+            held.into_iter().chain(borrow).reduce(Mutability::strictest)
+        };
+
+        let held_after_grant = |callee: NodeIndex, held: Option<Mutability>| match self.call_graph
+            [callee]
+            .grants
+            .get(component)
+            .map(|(m, _)| *m)
+        {
+            _ if held.is_none() => None,
+            Some(Mutability::Mutable) => None,
+            Some(Mutability::Immutable) => Some(Mutability::Immutable),
+            None => held,
+        };
+
+        let state_index = |node: NodeIndex, held: Option<Mutability>| {
+            node.index() * 3
+                + match held {
+                    None => 0,
+                    Some(Mutability::Immutable) => 1,
+                    Some(Mutability::Mutable) => 2,
+                }
+        };
+
+        // We search over the product of scopes and the way in which the component is held on entry.
+        // Allowing every state to be expanded up to `count` times yields the `count` shortest call
+        // chains in breadth-first order while keeping the search linear in the size of the graph.
+        let search = |roots: &[NodeIndex]| {
+            let mut arena = Vec::<(NodeIndex, Option<Mutability>, Option<usize>)>::new();
+            let mut expansions = vec![0; self.call_graph.node_count() * 3];
+            let mut queue = VecDeque::new();
+            let mut found = Vec::new();
+
+            for &root in roots {
+                queue.push_back(arena.len());
+                arena.push((root, None, None));
+            }
+
+            while let Some(item) = queue.pop_front() {
+                let (node, held, _) = arena[item];
+
+                if node == target && held.is_some() {
+                    found.push(item);
+                    if found.len() >= count {
+                        break;
+                    }
+                }
+
+                let expanded = &mut expansions[state_index(node, held)];
+                if *expanded >= count {
+                    continue;
+                }
+                *expanded += 1;
+
+                let mut seen_callees = FxHashSet::default();
+
+                for edge in self.call_graph.edges_directed(node, Direction::Outgoing) {
+                    let callee = edge.target();
+                    let callee_held =
+                        held_after_grant(callee, held_while_calling(node, edge.weight(), held));
+
+                    if seen_callees.insert((callee, callee_held)) {
+                        queue.push_back(arena.len());
+                        arena.push((callee, callee_held, Some(item)));
+                    }
+                }
+            }
+
+            (arena, found)
+        };
+
+        // Prefer chains starting at a root scope, be it configured or one without callers. If the
+        // component is only ever held within a cycle unreachable from any root, start from every
+        // scope instead.
+        let roots = if self.root_names.is_empty() {
+            self.call_graph
+                .node_indices()
+                .filter(|&idx| {
+                    self.call_graph
+                        .neighbors_directed(idx, Direction::Incoming)
+                        .all(|caller| caller == idx)
+                })
+                .collect::<Vec<_>>()
+        } else {
+            self.roots.iter().copied().collect()
+        };
+
+        let (mut arena, mut found) = search(&roots);
+
+        if found.is_empty() {
+            (arena, found) = search(&self.call_graph.node_indices().collect::<Vec<_>>());
+        }
+
+        // Reconstruct the chains.
+        let chains = found
+            .into_iter()
+            .map(|item| {
+                let mut path = Vec::new();
+                let mut cursor = Some(item);
+
+                while let Some(item) = cursor {
+                    path.push(arena[item]);
+                    cursor = arena[item].2;
+                }
+
+                path.reverse();
+
+                let mut steps = Vec::new();
+                let mut prev = None::<(NodeIndex, Option<Mutability>)>;
+
+                for (node, held, _) in path {
+                    let scope = &self.call_graph[node];
+                    let sites = |metas: &[BorrowMeta]| {
+                        metas
+                            .iter()
+                            .map(|meta| meta.def_path.to_string())
+                            .collect::<Vec<_>>()
+                    };
+
+                    let mut call_sites = Vec::new();
+                    let mut grant = None;
+
+                    if let Some((prev_node, prev_held)) = prev {
+                        // Only list the calls which lead to the component being held this way.
+                        let calls = self
+                            .call_graph
+                            .edges_connecting(prev_node, node)
+                            .filter(|edge| {
+                                held_after_grant(
+                                    node,
+                                    held_while_calling(prev_node, edge.weight(), prev_held),
+                                ) == held
+                            })
+                            .collect::<Vec<_>>();
+
+                        call_sites = calls
+                            .iter()
+                            .map(|edge| edge.weight().def_path.to_string())
+                            .collect();
+
+                        if calls.iter().any(|edge| {
+                            held_while_calling(prev_node, edge.weight(), prev_held).is_some()
+                        }) {
+                            grant = scope
+                                .grants
+                                .get(component)
+                                .map(|(mutability, metas)| (*mutability, sites(metas)));
+                        }
+                    }
+
+                    steps.push(WhyStep {
+                        scope: self.scope_ref(node),
+                        call_sites,
+                        grant,
+                        borrow: scope
+                            .borrows
+                            .get(component)
+                            .map(|(mutability, metas)| (*mutability, sites(metas))),
+                        held_on_entry: held,
+                    });
+
+                    prev = Some((node, held));
+                }
+
+                WhyChain {
+                    held: steps.last().unwrap().held_on_entry.unwrap(),
+                    steps,
+                }
+            })
+            .collect();
+
+        Ok(chains)
+    }
+}
+
+// === Snapshots === //
+
+/// Everything known about a scope, used to compare the scope graphs of two builds.
+#[derive(Debug, Clone)]
+pub struct ScopeSnapshot {
+    pub scope: ScopeRef,
+    pub borrows: BTreeMap<String, Mutability>,
+    pub transient_borrows: BTreeMap<String, Mutability>,
+    pub grants: BTreeMap<String, Mutability>,
+    /// The ids of the scopes this scope may call or dispatch to.
+    pub calls: BTreeSet<String>,
+    /// The way in which each component could possibly be borrowed when the scope is entered.
+    pub potentially_borrowed: BTreeMap<String, Mutability>,
+}
+
+impl Validator<'_> {
+    pub fn snapshot(&self) -> Vec<ScopeSnapshot> {
+        let (_, potentially_borrowed) = self.analyze();
+        let component_name = |id: &ComponentId| self.component_meta[id].name.clone();
+
+        self.call_graph
+            .node_indices()
+            .map(|idx| {
+                let scope = &self.call_graph[idx];
+
+                ScopeSnapshot {
+                    scope: self.scope_ref(idx),
+                    borrows: scope
+                        .borrows
+                        .iter()
+                        .map(|(id, (mutability, _))| (component_name(id), *mutability))
+                        .collect(),
+                    transient_borrows: scope
+                        .transient_borrows
+                        .iter()
+                        .map(|(id, (mutability, _))| (component_name(id), *mutability))
+                        .collect(),
+                    grants: scope
+                        .grants
+                        .iter()
+                        .map(|(id, (mutability, _))| (component_name(id), *mutability))
+                        .collect(),
+                    calls: self
+                        .call_graph
+                        .neighbors_directed(idx, Direction::Outgoing)
+                        .map(|callee| self.call_graph[callee].id.clone())
+                        .collect(),
+                    potentially_borrowed: potentially_borrowed[idx.index()]
+                        .iter()
+                        .map(|(id, mutability)| {
+                            (component_name(&ComponentId(id.clone(), [])), *mutability)
+                        })
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        baseline::{Baseline, BaselineEntry},
+        diagnostic::Summary,
+    };
+
+    use Mutability::{Immutable as Ref, Mutable as Mut};
+
+    fn leak(text: String) -> &'static str {
+        Box::leak(text.into_boxed_str())
+    }
+
+    /// Builds a validator whose scopes and components are named by their ids. Scopes are
+    /// registered as they are first mentioned.
+    struct Builder {
+        validator: Validator<'static>,
+    }
+
+    impl Builder {
+        fn new() -> Self {
+            Self::with_config(&Config::default())
+        }
+
+        fn with_config(config: &Config) -> Self {
+            Self {
+                validator: Validator::new(config),
+            }
+        }
+
+        fn scope_at(&mut self, id: &str, name: &str, defined_at: &str) -> ScopeId<'static> {
+            let scope = ScopeId(id.to_string(), []);
+            let meta = ScopeMeta {
+                _dummy: [],
+                name: name.to_string(),
+                defined_at: leak(defined_at.to_string()),
+            };
+
+            self.validator.register_scope(scope.clone(), meta);
+            scope
+        }
+
+        fn scope(&mut self, id: &str) -> ScopeId<'static> {
+            self.scope_at(id, id, &format!("src/{id}.rs:1:1"))
+        }
+
+        fn component(&mut self, id: &str) -> ComponentId<'static> {
+            let component = ComponentId(id.to_string(), []);
+            self.validator.annotate_component(
+                component.clone(),
+                ComponentMeta {
+                    _dummy: [],
+                    name: id.to_string(),
+                },
+            );
+            component
+        }
+
+        fn borrow_meta(scope: &str, mutability: Mutability) -> BorrowMeta<'static> {
+            BorrowMeta {
+                def_path: leak(format!("src/{scope}.rs:2:5")),
+                mutability,
+            }
+        }
+
+        fn call_meta(from: &str, to: &str, held: Option<&[&str]>) -> CallMeta<'static> {
+            CallMeta {
+                def_path: leak(format!("src/{from}.rs:{to}")),
+                held: held.map(|held| {
+                    &*Vec::leak(held.iter().map(|c| c.to_string()).collect::<Vec<_>>())
+                }),
+            }
+        }
+
+        fn borrow(&mut self, scope: &str, component: &str, mutability: Mutability) -> &mut Self {
+            let id = self.scope(scope);
+            let component = self.component(component);
+            let meta = Self::borrow_meta(scope, mutability);
+            self.validator
+                .push_access(&id, component, mutability, meta)
+                .unwrap();
+            self
+        }
+
+        fn grant(&mut self, scope: &str, component: &str, mutability: Mutability) -> &mut Self {
+            let id = self.scope(scope);
+            let component = self.component(component);
+            let meta = Self::borrow_meta(scope, mutability);
+            self.validator
+                .push_grant(&id, component, mutability, meta)
+                .unwrap();
+            self
+        }
+
+        fn transient(&mut self, scope: &str, component: &str, mutability: Mutability) -> &mut Self {
+            let id = self.scope(scope);
+            let component = self.component(component);
+            let meta = Self::borrow_meta(scope, mutability);
+            self.validator
+                .push_transient_access(&id, component, mutability, meta)
+                .unwrap();
+            self
+        }
+
+        fn hold(&mut self, scope: &str, component: &str, mutability: Mutability) -> &mut Self {
+            let id = self.scope(scope);
+            let component = self.component(component);
+            let meta = Self::borrow_meta(scope, mutability);
+            self.validator
+                .push_hold(&id, component, mutability, meta)
+                .unwrap();
+            self
+        }
+
+        fn task_root(&mut self, scope: &str) -> &mut Self {
+            let id = self.scope(scope);
+            self.validator.push_task_root(&id).unwrap();
+            self
+        }
+
+        fn call(&mut self, from: &str, to: &str) -> &mut Self {
+            let (from_id, to_id) = (self.scope(from), self.scope(to));
+            self.validator
+                .push_call_edge(&from_id, &to_id, Self::call_meta(from, to, None))
+                .unwrap();
+            self
+        }
+
+        fn call_holding(&mut self, from: &str, to: &str, held: &[&str]) -> &mut Self {
+            let (from_id, to_id) = (self.scope(from), self.scope(to));
+            self.validator
+                .push_call_edge(&from_id, &to_id, Self::call_meta(from, to, Some(held)))
+                .unwrap();
+            self
+        }
+
+        fn dispatch(&mut self, from: &str, point: &str) -> &mut Self {
+            let (from_id, point_id) = (self.scope(from), self.scope(point));
+            self.validator
+                .push_dispatch_edge(&from_id, &point_id, Self::call_meta(from, point, None))
+                .unwrap();
+            self
+        }
+
+        fn implement(&mut self, scope: &str, point: &str) -> &mut Self {
+            let (scope_id, point_id) = (self.scope(scope), self.scope(point));
+            self.validator.push_impl(&scope_id, &point_id).unwrap();
+            self
+        }
+
+        fn spawn(&mut self, from: &str, to: &str) -> &mut Self {
+            let (from_id, to_id) = (self.scope(from), self.scope(to));
+            self.validator
+                .push_spawn_edge(&from_id, &to_id, Self::call_meta(from, to, None))
+                .unwrap();
+            self
+        }
+
+        fn entry_point(&mut self, scope: &str, name: &'static str) -> &mut Self {
+            let id = self.scope(scope);
+            self.validator.push_entry_point(&id, name).unwrap();
+            self
+        }
+
+        fn call_at(&mut self, from: &str, to: &str, call_site: &'static str) -> &mut Self {
+            let (from_id, to_id) = (self.scope(from), self.scope(to));
+            let meta = CallMeta {
+                def_path: call_site,
+                held: None,
+            };
+            self.validator
+                .push_call_edge(&from_id, &to_id, meta)
+                .unwrap();
+            self
+        }
+
+        fn validate(&self) -> Vec<Diagnostic> {
+            self.validator.validate()
+        }
+    }
+
+    /// Summarizes diagnostics as their kind, scope and component.
+    fn conflicts(diagnostics: &[Diagnostic]) -> Vec<(ConflictKind, &str, &str)> {
+        let mut conflicts = diagnostics
+            .iter()
+            .map(|diag| (diag.kind, diag.scope.name.as_str(), diag.component.as_str()))
+            .collect::<Vec<_>>();
+        conflicts.sort_by_key(|&(kind, scope, component)| (kind.rule_id(), scope, component));
+        conflicts
+    }
+
+    #[test]
+    fn conflicting_borrow_is_reported() {
+        let diagnostics = Builder::new()
+            .borrow("outer", "u32", Mut)
+            .call("outer", "inner")
+            .borrow("inner", "u32", Ref)
+            .validate();
+
+        assert_eq!(
+            conflicts(&diagnostics),
+            [(ConflictKind::BorrowConflict, "inner", "u32")],
+        );
+        assert_eq!(diagnostics[0].held_mutability, Mut);
+    }
+
+    #[test]
+    fn shared_borrows_do_not_conflict() {
+        let diagnostics = Builder::new()
+            .borrow("outer", "u32", Ref)
+            .call("outer", "inner")
+            .borrow("inner", "u32", Ref)
+            .validate();
+
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn every_conflicting_borrow_is_reported() {
+        let diagnostics = Builder::new()
+            .borrow("outer", "u32", Mut)
+            .borrow("outer", "u64", Mut)
+            .borrow("outer", "u8", Ref)
+            .call("outer", "inner")
+            .borrow("inner", "u8", Ref)
+            .borrow("inner", "u32", Ref)
+            .borrow("inner", "u64", Mut)
+            .validate();
+
+        assert_eq!(
+            conflicts(&diagnostics),
+            [
+                (ConflictKind::BorrowConflict, "inner", "u32"),
+                (ConflictKind::BorrowConflict, "inner", "u64"),
+            ],
+        );
+
+        let summary = Summary::of(&diagnostics);
+        assert_eq!((summary.conflicts, summary.scopes), (2, 1));
+    }
+
+    #[test]
+    fn graph_is_sorted() {
+        let mut builder = Builder::new();
+        builder
+            .call("c", "a")
+            .call("c", "b")
+            .call("b", "a")
+            .borrow("b", "u32", Ref);
+
+        let graph = builder
+            .validator
+            .export_graph(&GraphFilter::default())
+            .unwrap();
+
+        let names = graph
+            .nodes
+            .iter()
+            .map(|node| node.scope.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["a", "b", "c"]);
+
+        let edges = graph
+            .edges
+            .iter()
+            .map(|edge| (edge.from, edge.to))
+            .collect::<Vec<_>>();
+        assert_eq!(edges, [(1, 0), (2, 0), (2, 1)]);
+    }
+
+    #[test]
+    fn dispatches_reach_implementations_declared_later() {
+        let diagnostics = Builder::new()
+            .borrow("outer", "u32", Mut)
+            .dispatch("outer", "Handler")
+            .implement("handler", "Handler")
+            .borrow("handler", "u32", Ref)
+            .validate();
+
+        assert_eq!(
+            conflicts(&diagnostics),
+            [(ConflictKind::BorrowConflict, "handler", "u32")],
+        );
+    }
+
+    #[test]
+    fn dispatches_reach_implementations_declared_earlier() {
+        let diagnostics = Builder::new()
+            .implement("first", "Handler")
+            .implement("second", "Handler")
+            .borrow("first", "u32", Ref)
+            .borrow("second", "u64", Ref)
+            .borrow("outer", "u32", Mut)
+            .dispatch("outer", "Handler")
+            .validate();
+
+        assert_eq!(
+            conflicts(&diagnostics),
+            [(ConflictKind::BorrowConflict, "first", "u32")],
+        );
+    }
+
+    #[test]
+    fn safe_recursion_is_accepted() {
+        let diagnostics = Builder::new()
+            .call("parse", "parse_list")
+            .call("parse_list", "parse")
+            .call("parse", "parse")
+            .borrow("parse", "Tokens", Ref)
+            .borrow("parse_list", "Tokens", Ref)
+            .validate();
+
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn recursion_holding_a_component_is_reported() {
+        let diagnostics = Builder::new()
+            .call("parse", "parse_list")
+            .call("parse_list", "parse")
+            .borrow("parse", "Tokens", Mut)
+            .validate();
+
+        assert_eq!(
+            conflicts(&diagnostics),
+            [(ConflictKind::ReentrantBorrow, "parse", "Tokens")],
+        );
+
+        let mut cycle = diagnostics[0]
+            .cycle
+            .iter()
+            .flatten()
+            .map(|member| member.scope.name.as_str())
+            .collect::<Vec<_>>();
+        cycle.sort();
+        assert_eq!(cycle, ["parse", "parse_list"]);
+    }
+
+    #[test]
+    fn direct_recursion_holding_a_component_is_reported() {
+        let diagnostics = Builder::new()
+            .call("visit", "visit")
+            .borrow("visit", "Tree", Mut)
+            .validate();
+
+        assert_eq!(
+            conflicts(&diagnostics),
+            [(ConflictKind::ReentrantBorrow, "visit", "Tree")],
+        );
+    }
+
+    #[test]
+    fn graph_is_filtered() {
+        let mut builder = Builder::new();
+        builder
+            .call("main", "physics")
+            .call("main", "render")
+            .call("physics", "collide")
+            .borrow("collide", "Bodies", Mut)
+            .borrow("render", "Frame", Mut);
+
+        let names = |filter: GraphFilter| {
+            builder
+                .validator
+                .export_graph(&filter)
+                .unwrap()
+                .nodes
+                .iter()
+                .map(|node| node.scope.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(GraphFilter {
+                reachable_from: Some("physics"),
+                ..GraphFilter::default()
+            }),
+            ["collide", "physics"],
+        );
+        assert_eq!(
+            names(GraphFilter {
+                reaching: Some("collide"),
+                ..GraphFilter::default()
+            }),
+            ["collide", "main", "physics"],
+        );
+        assert_eq!(
+            names(GraphFilter {
+                component: Some("Frame"),
+                ..GraphFilter::default()
+            }),
+            ["render"],
+        );
+    }
+
+    /// Lists the names of the scopes along each chain returned by a why query.
+    fn why_chains(
+        builder: &Builder,
+        scope: &str,
+        component: &str,
+        count: usize,
+    ) -> Vec<Vec<String>> {
+        builder
+            .validator
+            .why(scope, component, count)
+            .unwrap()
+            .iter()
+            .map(|chain| {
+                chain
+                    .steps
+                    .iter()
+                    .map(|step| step.scope.name.clone())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn why_lists_the_shortest_chains_first() {
+        let mut builder = Builder::new();
+        builder
+            .borrow("main", "u32", Mut)
+            .call("main", "helper")
+            .call("main", "physics")
+            .call("physics", "helper");
+
+        assert_eq!(
+            why_chains(&builder, "helper", "u32", 1),
+            [["main", "helper"]]
+        );
+        assert_eq!(
+            why_chains(&builder, "helper", "u32", 2),
+            [vec!["main", "helper"], vec!["main", "physics", "helper"]],
+        );
+
+        let chains = builder.validator.why("helper", "u32", 1).unwrap();
+        assert_eq!(chains[0].held, Mut);
+        assert_eq!(chains[0].steps[1].call_sites, ["src/main.rs:helper"]);
+    }
+
+    #[test]
+    fn why_accounts_for_grants() {
+        let mut builder = Builder::new();
+        builder
+            .borrow("main", "u32", Mut)
+            .call("main", "physics")
+            .grant("physics", "u32", Ref)
+            .call("physics", "helper")
+            .call("main", "render")
+            .grant("render", "u32", Mut)
+            .call("render", "draw");
+
+        let chains = builder.validator.why("helper", "u32", 1).unwrap();
+        assert_eq!(chains[0].held, Ref);
+        assert!(chains[0].steps[1].grant.is_some());
+
+        assert!(why_chains(&builder, "draw", "u32", 1).is_empty());
+    }
+
+    #[test]
+    fn why_rejects_unknown_names() {
+        let mut builder = Builder::new();
+        builder.borrow("main", "u32", Mut);
+
+        assert!(builder.validator.why("missing", "u32", 1).is_err());
+        assert!(builder.validator.why("main", "u64", 1).is_err());
+    }
+
+    /// Lists the names of the scopes in a diagnostic's explanation.
+    fn explained_scopes(diagnostic: &Diagnostic) -> Vec<&str> {
+        diagnostic
+            .explanation
+            .nodes
+            .iter()
+            .map(|node| node.scope.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn immutable_grant_is_explained() {
+        let diagnostics = Builder::new()
+            .borrow("outer", "u32", Mut)
+            .call("outer", "mid")
+            .grant("mid", "u32", Ref)
+            .call("mid", "inner")
+            .borrow("inner", "u32", Mut)
+            .validate();
+
+        assert_eq!(
+            conflicts(&diagnostics),
+            [(ConflictKind::BorrowConflict, "inner", "u32")],
+        );
+
+        let explanation = &diagnostics[0].explanation;
+        assert_eq!(explained_scopes(&diagnostics[0]), ["inner", "mid", "outer"]);
+        assert_eq!(explanation.nodes[1].granted_at.len(), 1);
+        assert_eq!(explanation.nodes[1].callers[0].held_mutability, Ref);
+    }
+
+    #[test]
+    fn mutable_grant_releases_responsibility() {
+        let diagnostics = Builder::new()
+            .borrow("outer", "u32", Mut)
+            .call("outer", "mid")
+            .grant("mid", "u32", Mut)
+            .borrow("mid", "u32", Ref)
+            .call("mid", "inner")
+            .borrow("inner", "u32", Mut)
+            .validate();
+
+        assert_eq!(
+            conflicts(&diagnostics),
+            [(ConflictKind::BorrowConflict, "inner", "u32")],
+        );
+        assert_eq!(explained_scopes(&diagnostics[0]), ["inner", "mid"]);
+    }
+
+    #[test]
+    fn shared_callers_are_explained_once() {
+        // A ladder of diamonds, which has exponentially many paths from its top to its bottom.
+        let mut builder = Builder::new();
+        builder.borrow("s0", "u32", Mut);
+
+        for i in 0..20 {
+            let (top, bottom) = (format!("s{i}"), format!("s{}", i + 1));
+            let (left, right) = (format!("l{i}"), format!("r{i}"));
+            builder
+                .call(&top, &left)
+                .call(&top, &right)
+                .call(&left, &bottom)
+                .call(&right, &bottom);
+        }
+
+        let diagnostics = builder.borrow("s20", "u32", Ref).validate();
+
+        assert_eq!(
+            conflicts(&diagnostics),
+            [(ConflictKind::BorrowConflict, "s20", "u32")],
+        );
+        assert_eq!(diagnostics[0].explanation.nodes.len(), 61);
+    }
+
+    #[test]
+    fn unknown_call_sites_name_the_caller() {
+        let diagnostics = Builder::new()
+            .borrow("outer", "u32", Mut)
+            .call_at("outer", "inner", "<unknown>")
+            .borrow("inner", "u32", Ref)
+            .validate();
+
+        let output = diagnostics[0].to_string();
+        assert!(
+            output.contains("An invocation declared by the scope outer at an unknown location"),
+            "{output}",
+        );
+    }
+
+    #[test]
+    fn scopes_without_definitions_are_listed() {
+        let mut builder = Builder::new();
+        builder
+            .validator
+            .register_definition("outer", "src/outer.rs:1:1");
+
+        builder
+            .borrow("outer", "u32", Mut)
+            .call("outer", "inner")
+            .borrow("inner", "u32", Ref);
+        builder.scope_at("old", "old", UNKNOWN_LOCATION);
+
+        let undefined = builder.validator.undefined_scopes();
+        let undefined = undefined
+            .iter()
+            .map(|scope| scope.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(undefined, ["inner"]);
+
+        assert_eq!(
+            conflicts(&builder.validate()),
+            [(ConflictKind::BorrowConflict, "inner", "u32")],
+        );
+    }
+
+    #[test]
+    fn unreached_definitions_are_listed() {
+        let mut builder = Builder::new();
+        builder
+            .validator
+            .register_definition("Unused", "src/unused.rs:1:1");
+        builder
+            .validator
+            .register_definition("outer", "src/outer.rs:1:1");
+        builder
+            .validator
+            .register_definition("Other", "src/lib.rs:8:1");
+        builder.call("outer", "inner");
+
+        let unreached = builder
+            .validator
+            .unreached_definitions()
+            .into_iter()
+            .map(|scope| (scope.name, scope.defined_at))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            unreached,
+            [
+                ("Other".to_string(), "src/lib.rs:8:1".to_string()),
+                ("Unused".to_string(), "src/unused.rs:1:1".to_string()),
+            ],
+        );
+    }
+
+    #[test]
+    fn conflicts_list_the_entry_points_reaching_them() {
+        let diagnostics = Builder::new()
+            .entry_point("outer", "tests::outer_is_valid")
+            .entry_point("outer", "tests::outer_is_fast")
+            .entry_point("other", "tests::other_is_valid")
+            .entry_point("inner", "tests::inner_is_valid")
+            .borrow("outer", "u32", Mut)
+            .call("outer", "inner")
+            .call("other", "inner")
+            .borrow("inner", "u32", Ref)
+            .validate();
+
+        assert_eq!(
+            conflicts(&diagnostics),
+            [(ConflictKind::BorrowConflict, "inner", "u32")],
+        );
+        assert_eq!(
+            diagnostics[0].reached_from,
+            ["tests::outer_is_fast", "tests::outer_is_valid"],
+        );
+    }
+
+    #[test]
+    fn only_scopes_reachable_from_roots_are_checked() {
+        let config = Config {
+            roots: vec!["main".to_string(), "missing".to_string()],
+            ..Config::default()
+        };
+
+        let mut builder = Builder::with_config(&config);
+        builder
+            .borrow("main", "u32", Mut)
+            .call("main", "inner")
+            .borrow("inner", "u32", Ref)
+            .borrow("unused", "u64", Mut)
+            .call("unused", "other")
+            .borrow("other", "u64", Ref);
+
+        assert_eq!(builder.validator.unmatched_roots(), ["missing"]);
+        assert_eq!(
+            conflicts(&builder.validate()),
+            [(ConflictKind::BorrowConflict, "inner", "u32")],
+        );
+    }
+
+    #[test]
+    fn ignored_components_are_not_checked() {
+        let config = Config {
+            ignored_components: vec!["RenderTag".to_string()],
+            ..Config::default()
+        };
+
+        let diagnostics = Builder::with_config(&config)
+            .borrow("outer", "app::RenderTag", Mut)
+            .borrow("outer", "u32", Mut)
+            .call("outer", "inner")
+            .borrow("inner", "app::RenderTag", Ref)
+            .borrow("inner", "u32", Ref)
+            .validate();
+
+        assert_eq!(
+            conflicts(&diagnostics),
+            [(ConflictKind::BorrowConflict, "inner", "u32")],
+        );
+    }
+
+    #[test]
+    fn lints_set_the_severity_of_conflicts() {
+        let build = |lints: Lints| {
+            Builder::with_config(&Config {
+                lints,
+                ..Config::default()
+            })
+            .borrow("outer", "u32", Mut)
+            .call("outer", "inner")
+            .borrow("inner", "u32", Ref)
+            .validate()
+        };
+
+        let warnings = build(Lints {
+            borrow_conflict: Severity::Warning,
+            ..Lints::default()
+        });
+        assert_eq!(warnings[0].severity, Severity::Warning);
+
+        let allowed = build(Lints {
+            borrow_conflict: Severity::Allow,
+            ..Lints::default()
+        });
+        assert!(allowed.is_empty());
+    }
+
+    #[test]
+    fn spawned_scopes_conflict_with_each_other() {
+        let diagnostics = Builder::new()
+            .spawn("schedule", "physics")
+            .spawn("schedule", "render")
+            .borrow("physics", "u32", Mut)
+            .call("render", "draw")
+            .borrow("draw", "u32", Ref)
+            .validate();
+
+        let conflicts = conflicts(&diagnostics);
+        assert!(!conflicts.is_empty());
+        assert!(conflicts.iter().all(|&(kind, _, component)| kind
+            == ConflictKind::ConcurrentAccess
+            && component == "u32"));
+
+        let concurrency = diagnostics[0].concurrency.as_ref().unwrap();
+        assert_eq!(concurrency.spawned_at.len(), 2);
+    }
+
+    #[test]
+    fn spawned_scopes_do_not_inherit_borrows() {
+        let diagnostics = Builder::new()
+            .borrow("schedule", "u32", Ref)
+            .spawn("schedule", "physics")
+            .borrow("physics", "u32", Ref)
+            .spawn("schedule", "render")
+            .borrow("render", "u64", Mut)
+            .validate();
+
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn spawned_scope_conflicts_with_its_spawner() {
+        let diagnostics = Builder::new()
+            .borrow("schedule", "u32", Mut)
+            .spawn("schedule", "physics")
+            .borrow("physics", "u32", Ref)
+            .validate();
+
+        assert!(conflicts(&diagnostics)
+            .iter()
+            .any(
+                |&(kind, _, component)| kind == ConflictKind::ConcurrentAccess
+                    && component == "u32"
+            ));
+    }
+
+    #[test]
+    fn borrow_held_across_await_conflicts_with_other_tasks() {
+        let diagnostics = Builder::new()
+            .task_root("connection")
+            .call("connection", "handle")
+            .hold("handle", "Sessions", Mut)
+            .task_root("cleanup")
+            .borrow("cleanup", "Sessions", Ref)
+            .validate();
+
+        assert_eq!(
+            conflicts(&diagnostics),
+            [(ConflictKind::SuspendedBorrow, "handle", "Sessions")],
+        );
+
+        let concurrency = diagnostics[0].concurrency.as_ref().unwrap();
+        assert_eq!(concurrency.other.name, "cleanup");
+        assert_eq!(
+            concurrency
+                .suspended_task
+                .as_ref()
+                .map(|task| task.name.as_str()),
+            Some("connection"),
+        );
+    }
+
+    #[test]
+    fn borrows_not_held_across_await_do_not_conflict() {
+        let diagnostics = Builder::new()
+            .task_root("connection")
+            .call("connection", "handle")
+            .borrow("handle", "Sessions", Mut)
+            .hold("handle", "Config", Ref)
+            .task_root("cleanup")
+            .borrow("cleanup", "Sessions", Mut)
+            .borrow("cleanup", "Config", Ref)
+            .validate();
+
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn transient_borrows_are_not_held_across_calls() {
+        let diagnostics = Builder::new()
+            .transient("outer", "u32", Mut)
+            .call("outer", "inner")
+            .borrow("inner", "u32", Mut)
+            .validate();
+
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn transient_borrows_are_checked_against_callers() {
+        let diagnostics = Builder::new()
+            .borrow("outer", "u32", Mut)
+            .call("outer", "inner")
+            .transient("inner", "u32", Ref)
+            .validate();
+
+        assert_eq!(
+            conflicts(&diagnostics),
+            [(ConflictKind::BorrowConflict, "inner", "u32")],
+        );
+        assert_eq!(diagnostics[0].explanation.nodes[0].borrowed_at.len(), 1);
+    }
+
+    #[test]
+    fn components_not_declared_as_held_are_released() {
+        let diagnostics = Builder::new()
+            .borrow("outer", "u32", Mut)
+            .borrow("outer", "u64", Ref)
+            .call_holding("outer", "inner", &["u64"])
+            .borrow("inner", "u32", Mut)
+            .validate();
+
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn components_declared_as_held_are_checked() {
+        let diagnostics = Builder::new()
+            .borrow("outer", "u32", Mut)
+            .borrow("outer", "u64", Ref)
+            .call_holding("outer", "inner", &["u64"])
+            .borrow("inner", "u64", Mut)
+            .validate();
+
+        assert_eq!(
+            conflicts(&diagnostics),
+            [(ConflictKind::BorrowConflict, "inner", "u64")],
+        );
+        assert_eq!(diagnostics[0].held_mutability, Ref);
+    }
+
+    #[test]
+    fn declared_holds_do_not_release_inherited_components() {
+        let diagnostics = Builder::new()
+            .borrow("root", "u32", Mut)
+            .call("root", "outer")
+            .call_holding("outer", "inner", &[])
+            .borrow("inner", "u32", Ref)
+            .validate();
+
+        assert_eq!(
+            conflicts(&diagnostics),
+            [(ConflictKind::BorrowConflict, "inner", "u32")],
+        );
+        assert_eq!(
+            explained_scopes(&diagnostics[0]),
+            ["inner", "outer", "root"],
+        );
+    }
+
+    /// Builds a conflict in `helper` which is reached through two callers.
+    fn conflict_through_two_callers(context_depth: usize) -> Vec<Diagnostic> {
+        let mut builder = Builder::new();
+        builder.validator.set_context_depth(context_depth);
+        builder
+            .borrow("physics", "u32", Mut)
+            .call("physics", "helper")
+            .borrow("render", "u32", Mut)
+            .call("render", "helper")
+            .borrow("helper", "u32", Ref)
+            .validate()
+    }
+
+    #[test]
+    fn contexts_are_grouped_per_conflict() {
+        let diagnostics = conflict_through_two_callers(3);
+
+        assert_eq!(
+            conflicts(&diagnostics),
+            [(ConflictKind::BorrowConflict, "helper", "u32")],
+        );
+
+        let mut callers = diagnostics[0]
+            .contexts
+            .iter()
+            .map(|context| {
+                assert_eq!(context.calls.len(), 1);
+                context.calls[0].caller.name.as_str()
+            })
+            .collect::<Vec<_>>();
+        callers.sort();
+        assert_eq!(callers, ["physics", "render"]);
+        assert_eq!(
+            explained_scopes(&diagnostics[0]),
+            ["helper", "physics", "render"],
+        );
+
+        let output = diagnostics[0].to_string();
+        assert!(
+            output.contains("is entered in any of the following contexts"),
+            "{output}",
+        );
+    }
+
+    #[test]
+    fn conflict_count_does_not_depend_on_context_depth() {
+        for context_depth in [0, 1, 3] {
+            let diagnostics = conflict_through_two_callers(context_depth);
+            assert_eq!(diagnostics.len(), 1, "context depth {context_depth}");
+            assert_eq!(
+                diagnostics[0].contexts.is_empty(),
+                context_depth == 0,
+                "context depth {context_depth}",
+            );
+        }
+    }
+
+    #[test]
+    fn baselines_do_not_depend_on_context_depth() {
+        let baseline_of = |diagnostics: Vec<Diagnostic>| {
+            let keys = ScopeKeys::new(diagnostics.iter().map(|diagnostic| &diagnostic.scope));
+            Baseline::of(
+                diagnostics
+                    .iter()
+                    .map(|diagnostic| BaselineEntry::of(diagnostic, &keys)),
+            )
+        };
+        let baseline = baseline_of(conflict_through_two_callers(0));
+        assert_eq!(baseline.conflicts.len(), 1);
+
+        for context_depth in [1, 3] {
+            let other = baseline_of(conflict_through_two_callers(context_depth));
+            assert_eq!(other.conflicts, baseline.conflicts);
+        }
+    }
+
+    #[test]
+    fn compatible_contexts_are_not_listed() {
+        let mut builder = Builder::new();
+        builder.validator.set_context_depth(3);
+        let diagnostics = builder
+            .borrow("physics", "u32", Mut)
+            .call("physics", "helper")
+            .borrow("render", "u32", Ref)
+            .call("render", "helper")
+            .borrow("helper", "u32", Ref)
+            .validate();
+
+        assert_eq!(
+            conflicts(&diagnostics),
+            [(ConflictKind::BorrowConflict, "helper", "u32")],
+        );
+        assert_eq!(diagnostics[0].contexts.len(), 1);
+        assert_eq!(diagnostics[0].contexts[0].calls[0].caller.name, "physics");
+        assert_eq!(explained_scopes(&diagnostics[0]), ["helper", "physics"]);
     }
 }
@@ -0,0 +1,105 @@
+//! Rendering of the call chains produced by [`Validator::why`](crate::validator::Validator::why).
+
+use std::fmt::Write;
+
+use crate::{
+    diagnostic::{Indent, INDENT_SIZE},
+    validator::{Mutability, WhyChain},
+};
+
+pub fn render_chains(scope: &str, component: &str, chains: &[WhyChain]) -> String {
+    let mut f = String::new();
+
+    if chains.is_empty() {
+        writeln!(
+            f,
+            "The component {component} is never held when the scope {scope} is entered.",
+        )
+        .unwrap();
+        return f;
+    }
+
+    for (i, chain) in chains.iter().enumerate() {
+        let target = chain.steps.last().unwrap();
+
+        writeln!(
+            f,
+            "Chain {}: the component {component} may be held {} when the scope {} is entered.",
+            i + 1,
+            chain.held.adjective(),
+            target.scope.name,
+        )
+        .unwrap();
+
+        for step in &chain.steps {
+            if !step.call_sites.is_empty() {
+                writeln!(
+                    f,
+                    "{}- which may call the scope {} defined at {} through the invocation declared at {}.",
+                    Indent(INDENT_SIZE),
+                    step.scope.name,
+                    step.scope.defined_at,
+                    step.call_sites.join(", "),
+                )
+                .unwrap();
+            } else {
+                writeln!(
+                    f,
+                    "{}- The scope {} defined at {}",
+                    Indent(INDENT_SIZE),
+                    step.scope.name,
+                    step.scope.defined_at,
+                )
+                .unwrap();
+            }
+
+            if let Some((mutability, sites)) = &step.grant {
+                writeln!(
+                    f,
+                    "{}  This scope grants the component {} at {}, {}.",
+                    Indent(INDENT_SIZE),
+                    mutability.adjective(),
+                    sites.join(", "),
+                    match mutability {
+                        Mutability::Immutable => "so it is only held immutably on entry",
+                        Mutability::Mutable => "so it is no longer held on entry",
+                    },
+                )
+                .unwrap();
+            }
+
+            // The borrow of the last scope is not responsible for the component being held on entry.
+            if std::ptr::eq(step, target) {
+                continue;
+            }
+
+            if let Some((mutability, sites)) = &step.borrow {
+                writeln!(
+                    f,
+                    "{}  This scope borrows the component {} at {}.",
+                    Indent(INDENT_SIZE),
+                    mutability.adjective(),
+                    sites.join(", "),
+                )
+                .unwrap();
+            }
+        }
+
+        writeln!(f).unwrap();
+    }
+
+    f
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_chains_are_explained() {
+        assert_eq!(
+            render_chains("Render", "u32", &[]),
+            "The component u32 is never held when the scope Render is entered.\n",
+        );
+    }
+}
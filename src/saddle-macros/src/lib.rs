@@ -0,0 +1,263 @@
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream},
+    parse_macro_input, parse_quote,
+    punctuated::Punctuated,
+    FnArg, Ident, ItemFn, Path, Token, Type,
+};
+
+/// A call declared in `calls(...)`, optionally naming the variable its token is bound to.
+struct CallArg {
+    binding: Option<Ident>,
+    ty: Type,
+}
+
+impl Parse for CallArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let binding = if input.peek(Ident) && input.peek2(Token![:]) && !input.peek2(Token![::]) {
+            let binding = input.parse()?;
+            input.parse::<Token![:]>()?;
+            Some(binding)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            binding,
+            ty: input.parse()?,
+        })
+    }
+}
+
+enum ScopeArg {
+    DepRef(Type),
+    DepMut(Type),
+    Calls(Punctuated<CallArg, Token![,]>),
+    Name(Ident),
+    Scope(Type),
+    Token(Ident),
+    Crate(Path),
+}
+
+impl Parse for ScopeArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![crate]) {
+            input.parse::<Token![crate]>()?;
+            input.parse::<Token![=]>()?;
+            return Ok(Self::Crate(input.parse()?));
+        }
+
+        if input.peek(Token![ref]) {
+            input.parse::<Token![ref]>()?;
+            return Ok(Self::DepRef(input.parse()?));
+        }
+
+        if input.peek(Token![mut]) {
+            input.parse::<Token![mut]>()?;
+            return Ok(Self::DepMut(input.parse()?));
+        }
+
+        let key = input.parse::<Ident>()?;
+
+        match key.to_string().as_str() {
+            "calls" => {
+                let content;
+                parenthesized!(content in input);
+                Ok(Self::Calls(
+                    content.parse_terminated(CallArg::parse, Token![,])?,
+                ))
+            }
+            "name" => {
+                input.parse::<Token![=]>()?;
+                Ok(Self::Name(input.parse()?))
+            }
+            "scope" => {
+                input.parse::<Token![=]>()?;
+                Ok(Self::Scope(input.parse()?))
+            }
+            "token" => {
+                input.parse::<Token![=]>()?;
+                Ok(Self::Token(input.parse()?))
+            }
+            _ => Err(syn::Error::new(
+                key.span(),
+                "expected `ref <type>`, `mut <type>`, `calls(...)`, `name = ...`, `scope = ...`, \
+                 `token = ...`, or `crate = ...`",
+            )),
+        }
+    }
+}
+
+fn default_scope_name(func: &Ident) -> Ident {
+    let mut name = String::new();
+
+    for part in func.to_string().trim_start_matches("r#").split('_') {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            name.extend(first.to_uppercase());
+            name.push_str(chars.as_str());
+        }
+    }
+
+    name.push_str("Scope");
+    Ident::new(&name, func.span())
+}
+
+/// Derives the name of the variable a called scope's token is bound to from the scope's type, e.g.
+/// `render` for `RenderScope`.
+fn default_call_binding(ty: &Type) -> syn::Result<Ident> {
+    let ident = match ty {
+        Type::Path(path) if path.qself.is_none() => path.path.segments.last().map(|s| &s.ident),
+        _ => None,
+    };
+
+    let Some(ident) = ident else {
+        return Err(syn::Error::new_spanned(
+            ty,
+            "the token of this call cannot be named after its type, name it with `name: Type`",
+        ));
+    };
+
+    let scope = ident.to_string();
+    let scope = scope
+        .strip_suffix("Scope")
+        .filter(|s| !s.is_empty())
+        .unwrap_or(&scope);
+    let mut name = String::new();
+
+    for (i, ch) in scope.chars().enumerate() {
+        if ch.is_uppercase() {
+            if i > 0 {
+                name.push('_');
+            }
+            name.extend(ch.to_lowercase());
+        } else {
+            name.push(ch);
+        }
+    }
+
+    Ok(Ident::new(&name, ident.span()))
+}
+
+/// Declares a scope for the annotated function along with the components it borrows and the
+/// scopes it calls.
+///
+/// ```ignore
+/// #[saddle::scoped(mut Position, ref Velocity, calls(RenderScope))]
+/// fn update(delta: f32) {
+///     // `c: &mut UpdateScope` and `render: &mut RenderScope` are in scope here.
+///     render_all(render);
+/// }
+/// ```
+///
+/// This generates a scope named after the function (`UpdateScope` above, overridable with
+/// `name = ...`) and inserts a token for it named `c` (overridable with `token = ...`) as the
+/// function's first non-receiver parameter. Since scopes cannot be defined inside `impl` blocks,
+/// methods must instead name a scope defined elsewhere with `scope = ...`.
+///
+/// Each scope listed in `calls(...)` is bound to a token for it, named after its type without the
+/// `Scope` suffix (`render` above) unless it is written as `name: Type`. The token does not borrow
+/// `c`, so it can be passed to the callee at any point of the body. Leaving it unused produces the
+/// usual unused variable warning since the declared call is then never made.
+///
+/// The generated code refers to the `saddle` crate as `::saddle`, which can be changed with
+/// `crate = path` when it is re-exported under another name.
+///
+/// This attribute is not named `scope` since attribute macros share a namespace with
+/// `macro_rules!` macros, so it would clash with `scope!`.
+#[proc_macro_attribute]
+pub fn scoped(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr with Punctuated::<ScopeArg, Token![,]>::parse_terminated);
+    let func = parse_macro_input!(item as ItemFn);
+
+    expand_scoped(args, func)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_scoped(
+    args: Punctuated<ScopeArg, Token![,]>,
+    mut func: ItemFn,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut deps = Vec::new();
+    let mut calls = Vec::new();
+    let mut name = None;
+    let mut existing_scope = None;
+    let mut token = Ident::new("c", Span::call_site());
+    let mut krate: Path = parse_quote!(::saddle);
+
+    for arg in args {
+        match arg {
+            ScopeArg::DepRef(ty) => deps.push((quote!(ref), ty)),
+            ScopeArg::DepMut(ty) => deps.push((quote!(mut), ty)),
+            ScopeArg::Calls(args) => calls.extend(args),
+            ScopeArg::Name(ident) => name = Some(ident),
+            ScopeArg::Scope(ty) => existing_scope = Some(ty),
+            ScopeArg::Token(ident) => token = ident,
+            ScopeArg::Crate(path) => krate = path,
+        }
+    }
+
+    if let (Some(name), Some(_)) = (&name, &existing_scope) {
+        return Err(syn::Error::new(
+            name.span(),
+            "`name` and `scope` cannot be used together",
+        ));
+    }
+
+    // Determine the scope type, generating a definition for it if necessary.
+    let (scope_ty, scope_def): (Type, _) = match existing_scope {
+        Some(ty) => (ty, None),
+        None => {
+            let name = name.unwrap_or_else(|| default_scope_name(&func.sig.ident));
+            let vis = &func.vis;
+
+            (
+                parse_quote!(#name),
+                Some(quote! { #krate::scope!(#vis #name); }),
+            )
+        }
+    };
+
+    // Inject the token parameter after the receiver, if any.
+    let insert_at = match func.sig.inputs.first() {
+        Some(FnArg::Receiver(_)) => 1,
+        _ => 0,
+    };
+
+    func.sig
+        .inputs
+        .insert(insert_at, parse_quote!(#token: &mut #scope_ty));
+
+    // Bind a token for each declared call.
+    let mut call_bindings = Vec::new();
+    let mut call_tys = Vec::new();
+
+    for call in calls {
+        call_bindings.push(match call.binding {
+            Some(binding) => binding,
+            None => default_call_binding(&call.ty)?,
+        });
+        call_tys.push(call.ty);
+    }
+
+    // Prepend the declarations to the body.
+    let (dep_kws, dep_tys): (Vec<_>, Vec<_>) = deps.into_iter().unzip();
+    let body = &func.block;
+    func.block = parse_quote!({
+        #(#krate::decl!(&*#token => #dep_kws #dep_tys);)*
+        #(
+            let #call_bindings: &mut #call_tys =
+                #krate::Scope::leak(#krate::decl!(&mut *#token => call #call_tys));
+        )*
+        #body
+    });
+
+    Ok(quote! {
+        #scope_def
+        #func
+    })
+}
@@ -1,4 +1,4 @@
-use saddle::{scope, Scope};
+use saddle::{cell::ScopedRefCell, decl, scope, Scope};
 
 fn main() {
     // This defines a new scope for our main function.
@@ -10,36 +10,46 @@ fn main() {
 
     // This call declares that our `MainScope` intends to call the scope `ExampleScope`, granting us
     // a token to call it. The type of the call target is inferred.
-    example(c.decl_call());
+    example(decl!(c => call));
 
-    my_sub_scoped_method(c.decl_call());
+    my_sub_scoped_method(decl!(c => call));
+
+    let counter = ScopedRefCell::new(0);
+    increment(decl!(c => call), &counter);
+
+    let handlers: Vec<Box<dyn Handler>> = vec![Box::new(Logger), Box::new(Counter)];
+    fire_event(decl!(c => call), &handlers);
+
+    update_physics(decl!(c => call), 0.5);
+
+    unlocated(decl!(c => call));
 }
 
 scope!(ExampleScope);
 
 fn example(c: &mut ExampleScope) {
-    // This call declares that our `ExampleScope` intends to access an `i32`. This method has no
+    // This declares that our `ExampleScope` intends to access an `i32`. This declaration has no
     // runtime behavior and is purely to introduce metadata into the binary for the validator to
     // read.
-    c.decl_dep_ref::<i32>();
+    decl!(c => ref i32);
 
     // Borrows can be declared in other functions and they will still contribute to the scope they
     // were passed.
     borrows_i32(c);
 
-    other_function(c.decl_call());
+    other_function(decl!(c => call));
 }
 
 fn borrows_i32(c: &impl Scope) {
-    c.decl_dep_ref::<i32>();
+    decl!(c => ref i32);
     // (borrow logic here)
 }
 
 scope!(OtherFunctionScope);
 
 fn other_function(c: &OtherFunctionScope) {
-    // This call declares that our `OtherFunctionScope` intends to access a `u32`.
-    c.decl_dep_mut::<u32>();
+    // This declares that our `OtherFunctionScope` intends to access a `u32`.
+    decl!(c => mut u32);
 
     other_function_helper(c);
 }
@@ -47,15 +57,15 @@ fn other_function(c: &OtherFunctionScope) {
 // Not every method is forced to introduce its own scope. Indeed, if we did introduce our own scope
 // here, we would get a warning from the saddle validator.
 fn other_function_helper(c: &OtherFunctionScope) {
-    // This call declares that our `OtherFunctionScope` intends to access a `u32`.
-    c.decl_dep_ref::<u32>();
+    // This declares that our `OtherFunctionScope` intends to access a `u32`.
+    decl!(c => ref u32);
 }
 
 scope!(MySubScopedMethodScope);
 
 fn my_sub_scoped_method(c: &mut MySubScopedMethodScope) {
     scope! { use c => c:  // Reads as scope `c` is used to call a new scope, whose token we bind to `c`.
-        c.decl_dep_ref::<u32>();
+        decl!(c => ref u32);
     }
 
     scope! { use c: // This is an alternative way to say the same thing.
@@ -68,12 +78,85 @@ fn my_sub_scoped_method(c: &mut MySubScopedMethodScope) {
 fn depends_upon_u32(c: &mut impl Scope) {
     // We can use `scope!` blocks to avoid having to name new public scopes for every new function.
     scope! { use c:
-        c.decl_dep_mut::<u32>();
+        decl!(c => mut u32);
     }
 
     // Alternatively, we could use the expression syntax.
     {
         let c = scope!(use c);
-        c.decl_dep_mut::<u32>();
+        decl!(c => mut u32);
+    }
+}
+
+scope!(IncrementScope);
+
+// `ScopedRefCell` declares the dependency to the validator on the token it is given and performs
+// the borrow in one step so the two can never get out of sync. The runtime tracker still expects the
+// scope to declare the dependency itself.
+fn increment(c: &IncrementScope, counter: &ScopedRefCell<u32>) {
+    decl!(c => mut u32);
+    *counter.borrow_mut(c) += 1;
+}
+
+// Scopes called through trait objects or function pointers cannot be named by their caller. Instead,
+// the caller dispatches through a "dispatch point" scope and every implementation declares that it
+// implements that dispatch point. The validator treats each dispatch as a call to every
+// implementation.
+scope!(OnEvent);
+
+trait Handler {
+    fn handle(&self, c: &mut OnEvent);
+}
+
+scope!(FireEventScope);
+
+fn fire_event(c: &mut FireEventScope, handlers: &[Box<dyn Handler>]) {
+    for handler in handlers {
+        handler.handle(decl!(c => dispatch));
     }
 }
+
+struct Logger;
+
+scope!(LoggerScope);
+
+impl Handler for Logger {
+    fn handle(&self, _c: &mut OnEvent) {
+        // Implementations which want a scope of their own can create it and declare that it
+        // implements the dispatch point.
+        let c = LoggerScope::new();
+        decl!(c => impl OnEvent);
+        decl!(c => ref i32);
+    }
+}
+
+struct Counter;
+
+impl Handler for Counter {
+    fn handle(&self, c: &mut OnEvent) {
+        // Implementations can also just use the dispatch point's token directly.
+        decl!(c => mut u64);
+    }
+}
+
+// The `scoped` attribute defines a scope for a function (here, `UpdatePhysicsScope`), inserts a
+// token parameter for it named `c`, and declares the listed dependencies and calls up front. Each
+// called scope is bound to a token named after it, here `on_event`.
+#[saddle::scoped(mut f64, ref i32, calls(OnEvent))]
+fn update_physics(delta: f32) {
+    let _ = delta;
+    decl!(c => ref u32);
+    Counter.handle(on_event);
+}
+
+scope!(UnlocatedScope);
+
+// Every form of `decl!` has an equivalent method on `Scope`. Unlike the macro, these methods cannot
+// record where they were called from, so the validator reports their declarations at `<unknown>`
+// unless it is given `--elf` or `--debug-info`.
+fn unlocated(c: &mut UnlocatedScope) {
+    c.decl_dep_ref::<i32>();
+    c.decl_dep_mut::<f32>();
+    other_function(c.decl_call());
+    c.decl_dep_ref::<u64>();
+}
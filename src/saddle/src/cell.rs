@@ -0,0 +1,103 @@
+use core::{
+    cell::{BorrowError, BorrowMutError, Ref, RefCell, RefMut},
+    fmt,
+    marker::PhantomData,
+};
+
+use crate::{mark_dep_mut, mark_dep_ref, Scope};
+
+#[cfg(feature = "tracker")]
+use crate::tracker::{self, BorrowKind};
+
+/// A [`RefCell`] whose borrows are declared as dependencies of the scope performing them.
+///
+/// The component declared to the validator is `T` itself. Use [`TaggedRefCell`] if several cells
+/// should be grouped under a single logical component.
+///
+/// With the `tracker` feature, each borrow is also checked against the dependencies the scope
+/// declared beforehand. The marker emitted by the borrow itself is only read by the validator and
+/// does not count as such a declaration.
+pub type ScopedRefCell<T> = TaggedRefCell<T, T>;
+
+/// A [`RefCell`] whose borrows are declared against the component `Tag` of the scope performing
+/// them.
+pub struct TaggedRefCell<Tag: ?Sized + 'static, T: ?Sized> {
+    _tag: PhantomData<fn() -> Tag>,
+    value: RefCell<T>,
+}
+
+impl<Tag: ?Sized + 'static, T> TaggedRefCell<Tag, T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            _tag: PhantomData,
+            value: RefCell::new(value),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<Tag: ?Sized + 'static, T: ?Sized> TaggedRefCell<Tag, T> {
+    #[track_caller]
+    pub fn borrow<'a>(&'a self, c: &impl Scope) -> Ref<'a, T> {
+        mark_dep_ref::<_, Tag>(c);
+        #[cfg(feature = "tracker")]
+        tracker::check_borrow::<Tag>(c, BorrowKind::Ref);
+        self.value.borrow()
+    }
+
+    #[track_caller]
+    pub fn borrow_mut<'a>(&'a self, c: &impl Scope) -> RefMut<'a, T> {
+        mark_dep_mut::<_, Tag>(c);
+        #[cfg(feature = "tracker")]
+        tracker::check_borrow::<Tag>(c, BorrowKind::Mut);
+        self.value.borrow_mut()
+    }
+
+    #[track_caller]
+    pub fn try_borrow<'a>(&'a self, c: &impl Scope) -> Result<Ref<'a, T>, BorrowError> {
+        mark_dep_ref::<_, Tag>(c);
+        #[cfg(feature = "tracker")]
+        tracker::check_borrow::<Tag>(c, BorrowKind::Ref);
+        self.value.try_borrow()
+    }
+
+    #[track_caller]
+    pub fn try_borrow_mut<'a>(&'a self, c: &impl Scope) -> Result<RefMut<'a, T>, BorrowMutError> {
+        mark_dep_mut::<_, Tag>(c);
+        #[cfg(feature = "tracker")]
+        tracker::check_borrow::<Tag>(c, BorrowKind::Mut);
+        self.value.try_borrow_mut()
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+
+    pub fn as_ptr(&self) -> *mut T {
+        self.value.as_ptr()
+    }
+}
+
+impl<Tag: ?Sized + 'static, T: Default> Default for TaggedRefCell<Tag, T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<Tag: ?Sized + 'static, T> From<T> for TaggedRefCell<Tag, T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<Tag: ?Sized + 'static, T: fmt::Debug> fmt::Debug for TaggedRefCell<Tag, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaggedRefCell")
+            .field("tag", &core::any::type_name::<Tag>())
+            .field("value", &self.value)
+            .finish()
+    }
+}
@@ -1,14 +1,34 @@
-#![no_std]
+#![cfg_attr(not(feature = "tracker"), no_std)]
 
-use core::{any::type_name, hint::black_box};
+use core::{any::type_name, hint::black_box, marker::PhantomData};
+
+pub use saddle_macros::scoped;
+
+pub mod cell;
+
+#[cfg(feature = "tracker")]
+pub mod tracker;
 
 #[doc(hidden)]
 pub mod scope_macro_internals {
-    use core::mem;
+    use core::{any::type_name, mem};
+
+    use crate::{
+        SaddleInternalV2DeclForCall, SaddleInternalV2DeclForCallHolding,
+        SaddleInternalV2DeclForDepMut, SaddleInternalV2DeclForDepRef,
+        SaddleInternalV2DeclForDepTransientMut, SaddleInternalV2DeclForDepTransientRef,
+        SaddleInternalV2DeclForDispatch, SaddleInternalV2DeclForGrantMut,
+        SaddleInternalV2DeclForGrantRef, SaddleInternalV2DeclForHoldMut,
+        SaddleInternalV2DeclForHoldRef, SaddleInternalV2DeclForImpl, SaddleInternalV2DeclForSpawn,
+        SaddleInternalV2DeclForTaskRoot,
+    };
+
+    #[cfg(feature = "tracker")]
+    use crate::tracker;
 
     pub use {
-        crate::{scope, Scope},
-        core::{column, line, mem::drop},
+        crate::{decl, scope, Scope},
+        core::{column, concat, file, hint::black_box, line, mem::drop, stringify},
         partial_scope::partial_shadow,
     };
 
@@ -16,7 +36,7 @@ pub mod scope_macro_internals {
         fn __saddle_internal_bind_scope(&mut self) -> BoundScopeProof<'_, Self>;
     }
 
-    impl<T: ?Sized + Scope> BindScopeAsRef for T {
+    impl<T: Scope> BindScopeAsRef for T {
         fn __saddle_internal_bind_scope(&mut self) -> BoundScopeProof<'_, Self> {
             BoundScopeProof(self)
         }
@@ -30,7 +50,165 @@ pub mod scope_macro_internals {
         }
     }
 
-    pub struct ScopeDisambiguator<T, const LINE: u32, const COLUMN: u32>(T);
+    pub struct ScopeDisambiguator<T, const FILE: u64, const LINE: u32, const COLUMN: u32>(T);
+
+    pub struct SourceLocation<const FILE: u64, const LINE: u32, const COLUMN: u32>;
+
+    /// The FNV-1a hash of a source file path. Markers refer to their file by this hash and the
+    /// file's actual path is recorded once in a separate `SaddleInternalV2File` marker.
+    pub const fn hash_file_path(path: &str) -> u64 {
+        let bytes = path.as_bytes();
+        let mut hash = 0xcbf29ce484222325_u64;
+        let mut i = 0;
+
+        while i < bytes.len() {
+            hash ^= bytes[i] as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+            i += 1;
+        }
+
+        hash
+    }
+
+    pub fn decl_dep_ref_at<S: Scope, T: ?Sized + 'static, L: 'static>(_c: &S) {
+        black_box(type_name::<
+            SaddleInternalV2DeclForDepRef<S::_InternalDisamb, T, L>,
+        >());
+
+        #[cfg(feature = "tracker")]
+        tracker::declare::<S, T>(tracker::BorrowKind::Ref);
+    }
+
+    pub fn decl_dep_mut_at<S: Scope, T: ?Sized + 'static, L: 'static>(_c: &S) {
+        black_box(type_name::<
+            SaddleInternalV2DeclForDepMut<S::_InternalDisamb, T, L>,
+        >());
+
+        #[cfg(feature = "tracker")]
+        tracker::declare::<S, T>(tracker::BorrowKind::Mut);
+    }
+
+    pub fn decl_dep_transient_ref_at<S: Scope, T: ?Sized + 'static, L: 'static>(_c: &S) {
+        black_box(type_name::<
+            SaddleInternalV2DeclForDepTransientRef<S::_InternalDisamb, T, L>,
+        >());
+
+        #[cfg(feature = "tracker")]
+        tracker::declare::<S, T>(tracker::BorrowKind::Ref);
+    }
+
+    pub fn decl_dep_transient_mut_at<S: Scope, T: ?Sized + 'static, L: 'static>(_c: &S) {
+        black_box(type_name::<
+            SaddleInternalV2DeclForDepTransientMut<S::_InternalDisamb, T, L>,
+        >());
+
+        #[cfg(feature = "tracker")]
+        tracker::declare::<S, T>(tracker::BorrowKind::Mut);
+    }
+
+    pub fn decl_grant_ref_at<S: Scope, T: ?Sized + 'static, L: 'static>(_c: &S) {
+        black_box(type_name::<
+            SaddleInternalV2DeclForGrantRef<S::_InternalDisamb, T, L>,
+        >());
+    }
+
+    pub fn decl_grant_mut_at<S: Scope, T: ?Sized + 'static, L: 'static>(_c: &S) {
+        black_box(type_name::<
+            SaddleInternalV2DeclForGrantMut<S::_InternalDisamb, T, L>,
+        >());
+    }
+
+    pub fn decl_hold_ref_at<S: Scope, T: ?Sized + 'static, L: 'static>(_c: &S) {
+        black_box(type_name::<
+            SaddleInternalV2DeclForHoldRef<S::_InternalDisamb, T, L>,
+        >());
+
+        #[cfg(feature = "tracker")]
+        tracker::declare::<S, T>(tracker::BorrowKind::Ref);
+    }
+
+    pub fn decl_hold_mut_at<S: Scope, T: ?Sized + 'static, L: 'static>(_c: &S) {
+        black_box(type_name::<
+            SaddleInternalV2DeclForHoldMut<S::_InternalDisamb, T, L>,
+        >());
+
+        #[cfg(feature = "tracker")]
+        tracker::declare::<S, T>(tracker::BorrowKind::Mut);
+    }
+
+    pub fn decl_task_root_at<S: Scope, L: 'static>(_c: &S) {
+        black_box(type_name::<
+            SaddleInternalV2DeclForTaskRoot<S::_InternalDisamb, (), L>,
+        >());
+    }
+
+    pub fn decl_call_at<S: Scope, G: Scope, L: 'static>(_c: &mut S) -> &mut G {
+        black_box(type_name::<
+            SaddleInternalV2DeclForCall<S::_InternalDisamb, G::_InternalDisamb, L>,
+        >());
+
+        #[cfg(feature = "tracker")]
+        tracker::enter_call::<S, G>();
+
+        G::new()
+    }
+
+    pub fn decl_call_holding_at<S: Scope, G: Scope, H: 'static, L: 'static>(_c: &mut S) -> &mut G {
+        black_box(type_name::<
+            SaddleInternalV2DeclForCallHolding<S::_InternalDisamb, G::_InternalDisamb, H, L>,
+        >());
+
+        #[cfg(feature = "tracker")]
+        tracker::enter_call::<S, G>();
+
+        G::new()
+    }
+
+    pub fn decl_dispatch_at<S: Scope, D: Scope, L: 'static>(_c: &mut S) -> &mut D {
+        black_box(type_name::<
+            SaddleInternalV2DeclForDispatch<S::_InternalDisamb, D::_InternalDisamb, L>,
+        >());
+
+        #[cfg(feature = "tracker")]
+        tracker::enter_call::<S, D>();
+
+        D::new()
+    }
+
+    pub fn decl_spawn_at<'t, S: Scope, G: Scope, L: 'static>(_c: &S) -> &'t mut G {
+        black_box(type_name::<
+            SaddleInternalV2DeclForSpawn<S::_InternalDisamb, G::_InternalDisamb, L>,
+        >());
+
+        #[cfg(feature = "tracker")]
+        tracker::enter_spawn::<S, G>();
+
+        G::new()
+    }
+
+    pub fn decl_impl_at<S: Scope, D: Scope, L: 'static>(_c: &S) {
+        black_box(type_name::<
+            SaddleInternalV2DeclForImpl<S::_InternalDisamb, D::_InternalDisamb, L>,
+        >());
+
+        #[cfg(feature = "tracker")]
+        tracker::enter_impl::<S, D>();
+    }
+
+    /// Copies a string into a byte array so that it can be stored in a `#[used]` static, which keeps
+    /// scope definition markers in the binary even if the scope is never used.
+    pub const fn str_to_bytes<const N: usize>(s: &str) -> [u8; N] {
+        let bytes = s.as_bytes();
+        let mut out = [0; N];
+        let mut i = 0;
+
+        while i < N {
+            out[i] = bytes[i];
+            i += 1;
+        }
+
+        out
+    }
 
     pub fn leak_zst<'a, T>(t: T) -> &'a mut T {
         assert_eq!(mem::size_of::<T>(), 0);
@@ -66,14 +244,34 @@ macro_rules! scope {
 		{
 			type _InternalDisamb = $crate::scope_macro_internals::ScopeDisambiguator<
 				Self,
+				{$crate::scope_macro_internals::hash_file_path($crate::scope_macro_internals::file!())},
 				{$crate::scope_macro_internals::line!()},
 				{$crate::scope_macro_internals::column!()},
 			>;
 
             fn new<'a>() -> &'a mut Self {
+                $crate::scope_macro_internals::decl!(@__file);
                 $crate::scope_macro_internals::leak_zst(Self { _private: [] })
             }
         }
+
+		const _: () = {
+			const DEFINITION: &str = $crate::scope_macro_internals::concat!(
+				"SaddleInternalV2Scope:",
+				$crate::scope_macro_internals::stringify!($name $(<$($generic),*>)?),
+				":",
+				$crate::scope_macro_internals::file!(),
+				":",
+				$crate::scope_macro_internals::line!(),
+				":",
+				$crate::scope_macro_internals::column!(),
+				"\0",
+			);
+
+			#[used]
+			static SADDLE_SCOPE_DEFINITION: [u8; DEFINITION.len()] =
+				$crate::scope_macro_internals::str_to_bytes(DEFINITION);
+		};
     )*};
 	(use $from:expr $(, inherits $($grant_kw:ident $grant_ty:ty),*$(,)?)?) => {
 		$crate::scope_macro_internals::mark_as_must_use({
@@ -87,9 +285,15 @@ macro_rules! scope {
             $crate::scope_macro_internals::scope!(InlineBlock<Caller>);
 
 			fn decl_call<C: 'static + $crate::scope_macro_internals::Scope>(caller: &mut C) -> &mut InlineBlock<C> {
-				$crate::scope_macro_internals::Scope::decl_call(caller)
+				$crate::scope_macro_internals::decl_call_at::<
+					C,
+					InlineBlock<C>,
+					$crate::scope_macro_internals::decl!(@__loc),
+				>(caller)
 			}
 
+			$crate::scope_macro_internals::decl!(@__file);
+
 			let to = decl_call(from);
 
 			$($($crate::scope_macro_internals::scope!(@__decl_grant to, $grant_kw $grant_ty);)*)?
@@ -121,19 +325,119 @@ macro_rules! scope {
         }
     };
 	(@__decl_dep $target:expr, ref $ty:ty) => {
-		$crate::scope_macro_internals::Scope::decl_dep_ref::<$ty>($target);
+		$crate::scope_macro_internals::decl!($target => ref $ty);
 	};
 	(@__decl_dep $target:expr, mut $ty:ty) => {
-		$crate::scope_macro_internals::Scope::decl_dep_mut::<$ty>($target);
+		$crate::scope_macro_internals::decl!($target => mut $ty);
 	};
 	(@__decl_grant $target:expr, ref $ty:ty) => {
-		$crate::scope_macro_internals::Scope::decl_grant_ref::<$ty>($target);
+		$crate::scope_macro_internals::decl!($target => grant ref $ty);
 	};
 	(@__decl_grant $target:expr, mut $ty:ty) => {
-		$crate::scope_macro_internals::Scope::decl_grant_mut::<$ty>($target);
+		$crate::scope_macro_internals::decl!($target => grant mut $ty);
+	};
+}
+
+/// Declares a dependency, grant, call, dispatch, dispatch implementation, spawn, or async task on a
+/// scope token like the corresponding `decl_*` method of [`Scope`] while also recording the location of the declaration
+/// for the validator's diagnostics.
+///
+/// ```ignore
+/// decl!(c => ref Position);
+/// decl!(c => mut Velocity);
+/// decl!(c => transient mut Velocity);
+/// decl!(c => grant ref Position);
+/// decl!(c => hold mut Connection);
+/// callee(decl!(c => call));
+/// render(decl!(c => call RenderScope, holding (Position, Velocity)));
+/// handler.handle(decl!(c => dispatch));
+/// decl!(c => impl OnEvent);
+/// let worker = decl!(c => spawn WorkerScope);
+/// decl!(c => task);
+/// ```
+#[macro_export]
+macro_rules! decl {
+	($target:expr => ref $ty:ty) => {{
+		$crate::scope_macro_internals::decl!(@__file);
+		$crate::scope_macro_internals::decl_dep_ref_at::<_, $ty, $crate::scope_macro_internals::decl!(@__loc)>($target)
+	}};
+	($target:expr => mut $ty:ty) => {{
+		$crate::scope_macro_internals::decl!(@__file);
+		$crate::scope_macro_internals::decl_dep_mut_at::<_, $ty, $crate::scope_macro_internals::decl!(@__loc)>($target)
+	}};
+	($target:expr => transient ref $ty:ty) => {{
+		$crate::scope_macro_internals::decl!(@__file);
+		$crate::scope_macro_internals::decl_dep_transient_ref_at::<_, $ty, $crate::scope_macro_internals::decl!(@__loc)>($target)
+	}};
+	($target:expr => transient mut $ty:ty) => {{
+		$crate::scope_macro_internals::decl!(@__file);
+		$crate::scope_macro_internals::decl_dep_transient_mut_at::<_, $ty, $crate::scope_macro_internals::decl!(@__loc)>($target)
+	}};
+	($target:expr => grant ref $ty:ty) => {{
+		$crate::scope_macro_internals::decl!(@__file);
+		$crate::scope_macro_internals::decl_grant_ref_at::<_, $ty, $crate::scope_macro_internals::decl!(@__loc)>($target)
+	}};
+	($target:expr => grant mut $ty:ty) => {{
+		$crate::scope_macro_internals::decl!(@__file);
+		$crate::scope_macro_internals::decl_grant_mut_at::<_, $ty, $crate::scope_macro_internals::decl!(@__loc)>($target)
+	}};
+	($target:expr => hold ref $ty:ty) => {{
+		$crate::scope_macro_internals::decl!(@__file);
+		$crate::scope_macro_internals::decl_hold_ref_at::<_, $ty, $crate::scope_macro_internals::decl!(@__loc)>($target)
+	}};
+	($target:expr => hold mut $ty:ty) => {{
+		$crate::scope_macro_internals::decl!(@__file);
+		$crate::scope_macro_internals::decl_hold_mut_at::<_, $ty, $crate::scope_macro_internals::decl!(@__loc)>($target)
+	}};
+	($target:expr => task) => {{
+		$crate::scope_macro_internals::decl!(@__file);
+		$crate::scope_macro_internals::decl_task_root_at::<_, $crate::scope_macro_internals::decl!(@__loc)>($target)
+	}};
+	($target:expr => call $($ty:ty)?, holding $held:ty) => {{
+		$crate::scope_macro_internals::decl!(@__file);
+		$crate::scope_macro_internals::decl_call_holding_at::<_, $crate::scope_macro_internals::decl!(@__or_infer $($ty)?), $held, $crate::scope_macro_internals::decl!(@__loc)>($target)
+	}};
+	($target:expr => call $($ty:ty)?) => {{
+		$crate::scope_macro_internals::decl!(@__file);
+		$crate::scope_macro_internals::decl_call_at::<_, $crate::scope_macro_internals::decl!(@__or_infer $($ty)?), $crate::scope_macro_internals::decl!(@__loc)>($target)
+	}};
+	($target:expr => dispatch $($ty:ty)?) => {{
+		$crate::scope_macro_internals::decl!(@__file);
+		$crate::scope_macro_internals::decl_dispatch_at::<_, $crate::scope_macro_internals::decl!(@__or_infer $($ty)?), $crate::scope_macro_internals::decl!(@__loc)>($target)
+	}};
+	($target:expr => spawn $($ty:ty)?) => {{
+		$crate::scope_macro_internals::decl!(@__file);
+		$crate::scope_macro_internals::decl_spawn_at::<_, $crate::scope_macro_internals::decl!(@__or_infer $($ty)?), $crate::scope_macro_internals::decl!(@__loc)>($target)
+	}};
+	($target:expr => impl $ty:ty) => {{
+		$crate::scope_macro_internals::decl!(@__file);
+		$crate::scope_macro_internals::decl_impl_at::<_, $ty, $crate::scope_macro_internals::decl!(@__loc)>($target)
+	}};
+	(@__or_infer $ty:ty) => { $ty };
+	(@__or_infer) => { _ };
+	(@__loc) => {
+		$crate::scope_macro_internals::SourceLocation<
+			{$crate::scope_macro_internals::hash_file_path($crate::scope_macro_internals::file!())},
+			{$crate::scope_macro_internals::line!()},
+			{$crate::scope_macro_internals::column!()},
+		>
+	};
+	(@__file) => {
+		$crate::scope_macro_internals::black_box($crate::scope_macro_internals::concat!(
+			"SaddleInternalV2File:",
+			$crate::scope_macro_internals::file!(),
+			"\0",
+		));
 	};
 }
 
+/// A token for a scope, through which the scope's dependencies and calls are declared.
+///
+/// Each `decl_*` method is equivalent to a form of [`decl!`]. Since the validator reads
+/// declarations from the types named in the binary, the location of a declaration must be part of
+/// its marker's type, which a method cannot obtain from its caller even with `#[track_caller]`.
+/// The validator therefore reports declarations made through these methods at `<unknown>` and
+/// [`decl!`] should be preferred.
 pub trait Scope: 'static + Sized {
     type _InternalDisamb: Sized;
 
@@ -143,42 +447,191 @@ pub trait Scope: 'static + Sized {
         Self::new()
     }
 
-    fn decl_dep_ref<T: 'static>(&self) {
+    fn decl_dep_ref<T: ?Sized + 'static>(&self) {
+        mark_dep_ref::<Self, T>(self);
+
+        #[cfg(feature = "tracker")]
+        tracker::declare::<Self, T>(tracker::BorrowKind::Ref);
+    }
+
+    fn decl_dep_mut<T: ?Sized + 'static>(&self) {
+        mark_dep_mut::<Self, T>(self);
+
+        #[cfg(feature = "tracker")]
+        tracker::declare::<Self, T>(tracker::BorrowKind::Mut);
+    }
+
+    /// Declares a dependency on `T` which is released before this scope calls into any other scope.
+    /// It is checked against the borrows of this scope's callers but not held on behalf of its
+    /// callees.
+    fn decl_dep_transient_ref<T: ?Sized + 'static>(&self) {
         black_box(type_name::<
-            SaddleInternalV1DeclForDepRef<Self::_InternalDisamb, T>,
+            SaddleInternalV1DeclForDepTransientRef<Self::_InternalDisamb, T>,
         >());
+
+        #[cfg(feature = "tracker")]
+        tracker::declare::<Self, T>(tracker::BorrowKind::Ref);
     }
 
-    fn decl_dep_mut<T: 'static>(&self) {
+    fn decl_dep_transient_mut<T: ?Sized + 'static>(&self) {
         black_box(type_name::<
-            SaddleInternalV1DeclForDepMut<Self::_InternalDisamb, T>,
+            SaddleInternalV1DeclForDepTransientMut<Self::_InternalDisamb, T>,
         >());
+
+        #[cfg(feature = "tracker")]
+        tracker::declare::<Self, T>(tracker::BorrowKind::Mut);
     }
 
-    fn decl_grant_ref<T: 'static>(&self) {
+    fn decl_grant_ref<T: ?Sized + 'static>(&self) {
         black_box(type_name::<
             SaddleInternalV1DeclForGrantRef<Self::_InternalDisamb, T>,
         >());
     }
 
-    fn decl_grant_mut<T: 'static>(&self) {
+    fn decl_grant_mut<T: ?Sized + 'static>(&self) {
         black_box(type_name::<
             SaddleInternalV1DeclForGrantMut<Self::_InternalDisamb, T>,
         >());
     }
 
+    /// Declares a dependency on `T` which may still be held when the scope's async task is
+    /// suspended at an `.await`.
+    fn decl_hold_ref<T: ?Sized + 'static>(&self) {
+        black_box(type_name::<
+            SaddleInternalV1DeclForHoldRef<Self::_InternalDisamb, T>,
+        >());
+
+        #[cfg(feature = "tracker")]
+        tracker::declare::<Self, T>(tracker::BorrowKind::Ref);
+    }
+
+    fn decl_hold_mut<T: ?Sized + 'static>(&self) {
+        black_box(type_name::<
+            SaddleInternalV1DeclForHoldMut<Self::_InternalDisamb, T>,
+        >());
+
+        #[cfg(feature = "tracker")]
+        tracker::declare::<Self, T>(tracker::BorrowKind::Mut);
+    }
+
+    /// Declares that this scope is the root of an async task, which may be interleaved with every
+    /// other task whenever one of them is suspended.
+    fn decl_task_root(&self) {
+        black_box(type_name::<
+            SaddleInternalV1DeclForTaskRoot<Self::_InternalDisamb, ()>,
+        >());
+    }
+
     fn decl_call<G: Scope>(&mut self) -> &mut G {
         black_box(type_name::<
             SaddleInternalV1DeclForCall<Self::_InternalDisamb, G::_InternalDisamb>,
         >());
 
+        #[cfg(feature = "tracker")]
+        tracker::enter_call::<Self, G>();
+
+        G::new()
+    }
+
+    /// Declares a call into `G` during which only the components listed in the tuple `H` remain
+    /// borrowed by this scope. Without this, every component this scope borrows is assumed to be
+    /// held across all of its calls. Components borrowed by this scope's callers are unaffected.
+    fn decl_call_holding<G: Scope, H: 'static>(&mut self) -> &mut G {
+        black_box(type_name::<
+            SaddleInternalV1DeclForCallHolding<Self::_InternalDisamb, G::_InternalDisamb, H>,
+        >());
+
+        #[cfg(feature = "tracker")]
+        tracker::enter_call::<Self, G>();
+
+        G::new()
+    }
+
+    fn decl_dispatch<D: Scope>(&mut self) -> &mut D {
+        black_box(type_name::<
+            SaddleInternalV1DeclForDispatch<Self::_InternalDisamb, D::_InternalDisamb>,
+        >());
+
+        #[cfg(feature = "tracker")]
+        tracker::enter_call::<Self, D>();
+
+        D::new()
+    }
+
+    /// Declares that the scope `G` may be started on another thread while this scope is running.
+    /// Spawned scopes are assumed to be joined before the spawning scope returns.
+    ///
+    /// The lifetime of the returned token is not bounded by `&self` so that both scopes can run at
+    /// the same time. A token borrowing this one would prevent this scope from calling into other
+    /// scopes, which requires `&mut self`, until the spawned scope is joined. Like the tokens
+    /// returned by [`Scope::leak`], the token is a zero-sized marker, so nothing is unsound about
+    /// it outliving this one. Only the validator's assumption that the spawned scope is joined in
+    /// time is.
+    fn decl_spawn<'t, G: Scope>(&self) -> &'t mut G {
+        black_box(type_name::<
+            SaddleInternalV1DeclForSpawn<Self::_InternalDisamb, G::_InternalDisamb>,
+        >());
+
+        #[cfg(feature = "tracker")]
+        tracker::enter_spawn::<Self, G>();
+
         G::new()
     }
+
+    fn decl_impl<D: Scope>(&self) {
+        black_box(type_name::<
+            SaddleInternalV1DeclForImpl<Self::_InternalDisamb, D::_InternalDisamb>,
+        >());
+
+        #[cfg(feature = "tracker")]
+        tracker::enter_impl::<Self, D>();
+    }
 }
 
-struct SaddleInternalV1DeclForDepRef<F, T>(F, T);
-struct SaddleInternalV1DeclForDepMut<F, T>(F, T);
-struct SaddleInternalV1DeclForGrantRef<F, T>(F, T);
-struct SaddleInternalV1DeclForGrantMut<F, T>(F, T);
+/// Emits the marker of [`Scope::decl_dep_ref`] for the validator without recording the dependency
+/// with the runtime tracker.
+pub(crate) fn mark_dep_ref<S: Scope, T: ?Sized + 'static>(_c: &S) {
+    black_box(type_name::<
+        SaddleInternalV1DeclForDepRef<S::_InternalDisamb, T>,
+    >());
+}
+
+/// Emits the marker of [`Scope::decl_dep_mut`] for the validator without recording the dependency
+/// with the runtime tracker.
+pub(crate) fn mark_dep_mut<S: Scope, T: ?Sized + 'static>(_c: &S) {
+    black_box(type_name::<
+        SaddleInternalV1DeclForDepMut<S::_InternalDisamb, T>,
+    >());
+}
+
+struct SaddleInternalV1DeclForDepRef<F, T: ?Sized>(F, PhantomData<T>);
+struct SaddleInternalV1DeclForDepMut<F, T: ?Sized>(F, PhantomData<T>);
+struct SaddleInternalV1DeclForDepTransientRef<F, T: ?Sized>(F, PhantomData<T>);
+struct SaddleInternalV1DeclForDepTransientMut<F, T: ?Sized>(F, PhantomData<T>);
+struct SaddleInternalV1DeclForGrantRef<F, T: ?Sized>(F, PhantomData<T>);
+struct SaddleInternalV1DeclForGrantMut<F, T: ?Sized>(F, PhantomData<T>);
+struct SaddleInternalV1DeclForHoldRef<F, T: ?Sized>(F, PhantomData<T>);
+struct SaddleInternalV1DeclForHoldMut<F, T: ?Sized>(F, PhantomData<T>);
+struct SaddleInternalV1DeclForTaskRoot<F, T>(F, T);
 
 struct SaddleInternalV1DeclForCall<F, G>(F, G);
+struct SaddleInternalV1DeclForCallHolding<F, G, H>(F, G, PhantomData<H>);
+struct SaddleInternalV1DeclForDispatch<F, D>(F, D);
+struct SaddleInternalV1DeclForImpl<F, D>(F, D);
+struct SaddleInternalV1DeclForSpawn<F, G>(F, G);
+
+struct SaddleInternalV2DeclForDepRef<F, T: ?Sized, L>(F, PhantomData<T>, L);
+struct SaddleInternalV2DeclForDepMut<F, T: ?Sized, L>(F, PhantomData<T>, L);
+struct SaddleInternalV2DeclForDepTransientRef<F, T: ?Sized, L>(F, PhantomData<T>, L);
+struct SaddleInternalV2DeclForDepTransientMut<F, T: ?Sized, L>(F, PhantomData<T>, L);
+struct SaddleInternalV2DeclForGrantRef<F, T: ?Sized, L>(F, PhantomData<T>, L);
+struct SaddleInternalV2DeclForGrantMut<F, T: ?Sized, L>(F, PhantomData<T>, L);
+struct SaddleInternalV2DeclForHoldRef<F, T: ?Sized, L>(F, PhantomData<T>, L);
+struct SaddleInternalV2DeclForHoldMut<F, T: ?Sized, L>(F, PhantomData<T>, L);
+struct SaddleInternalV2DeclForTaskRoot<F, T, L>(F, T, L);
+
+struct SaddleInternalV2DeclForCall<F, G, L>(F, G, L);
+struct SaddleInternalV2DeclForCallHolding<F, G, H, L>(F, G, PhantomData<H>, L);
+struct SaddleInternalV2DeclForDispatch<F, D, L>(F, D, L);
+struct SaddleInternalV2DeclForImpl<F, D, L>(F, D, L);
+struct SaddleInternalV2DeclForSpawn<F, G, L>(F, G, L);
@@ -0,0 +1,207 @@
+//! A debug-only runtime cross-check of declared dependencies against the borrows actually being
+//! performed.
+//!
+//! The tracker maintains a thread-local stack of the scopes currently being executed. A scope is
+//! pushed whenever it is entered through [`Scope::decl_call`] (which includes `scope!(use ...)`
+//! blocks), [`Scope::decl_dispatch`] or, for implementations of a dispatch point,
+//! [`Scope::decl_impl`]. Everything above a scope is popped as soon as its token is used again
+//! since, by that point, all the scopes it called into must have returned. A token for a scope
+//! which an entry of the stack has declared a call to re-enters that scope on top of its caller,
+//! which lets a token obtained ahead of the call be used after the caller's own token. Spawns
+//! declared through [`Scope::decl_spawn`] are recorded the same way without entering the spawned
+//! scope, which is entered once its token is used, either as the root of another thread's stack or
+//! on top of its spawner if it runs on the same thread. Other tokens which are not part of the
+//! stack while it is non-empty (e.g. tokens obtained through [`Scope::new`] or
+//! [`Scope::leak`] in the middle of another scope) do not contribute to it and their borrows are
+//! checked against the scope which is actually active.
+
+use std::{
+    any::type_name,
+    cell::{Cell, RefCell},
+};
+
+use crate::Scope;
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub enum BorrowKind {
+    Ref,
+    Mut,
+}
+
+impl BorrowKind {
+    fn adjective(self) -> &'static str {
+        match self {
+            BorrowKind::Ref => "immutably",
+            BorrowKind::Mut => "mutably",
+        }
+    }
+
+    fn is_covered_by(self, declared: BorrowKind) -> bool {
+        matches!(
+            (self, declared),
+            (BorrowKind::Ref, _) | (BorrowKind::Mut, BorrowKind::Mut)
+        )
+    }
+}
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Default)]
+pub enum ViolationMode {
+    #[default]
+    Panic,
+    Log,
+}
+
+struct Frame {
+    scope: &'static str,
+    deps: Vec<(&'static str, BorrowKind)>,
+    callees: Vec<&'static str>,
+}
+
+impl Frame {
+    fn new(scope: &'static str) -> Self {
+        Self {
+            scope,
+            deps: Vec::new(),
+            callees: Vec::new(),
+        }
+    }
+}
+
+thread_local! {
+    static STACK: RefCell<Vec<Frame>> = const { RefCell::new(Vec::new()) };
+    static MODE: Cell<ViolationMode> = const { Cell::new(ViolationMode::Panic) };
+}
+
+fn scope_name<S: Scope>() -> &'static str {
+    type_name::<S::_InternalDisamb>()
+}
+
+/// Truncates the stack to the frame of `scope`, returning whether that scope is now the active
+/// one. A scope which is not on the stack but which one of its entries declared a call to is
+/// re-entered on top of that entry. An empty stack adopts `scope` as its root.
+fn activate(stack: &mut Vec<Frame>, scope: &'static str) -> bool {
+    if let Some(pos) = stack.iter().rposition(|frame| frame.scope == scope) {
+        stack.truncate(pos + 1);
+        true
+    } else if let Some(pos) = stack
+        .iter()
+        .rposition(|frame| frame.callees.contains(&scope))
+    {
+        stack.truncate(pos + 1);
+        stack.push(Frame::new(scope));
+        true
+    } else if stack.is_empty() {
+        stack.push(Frame::new(scope));
+        true
+    } else {
+        false
+    }
+}
+
+pub(crate) fn declare<S: Scope, T: ?Sized + 'static>(kind: BorrowKind) {
+    STACK.with_borrow_mut(|stack| {
+        if activate(stack, scope_name::<S>()) {
+            stack
+                .last_mut()
+                .unwrap()
+                .deps
+                .push((type_name::<T>(), kind));
+        }
+    });
+}
+
+pub(crate) fn enter_call<S: Scope, G: Scope>() {
+    STACK.with_borrow_mut(|stack| {
+        let callee = scope_name::<G>();
+
+        if activate(stack, scope_name::<S>()) {
+            let caller = stack.last_mut().unwrap();
+            if !caller.callees.contains(&callee) {
+                caller.callees.push(callee);
+            }
+        }
+
+        stack.push(Frame::new(callee));
+    });
+}
+
+pub(crate) fn enter_spawn<S: Scope, G: Scope>() {
+    STACK.with_borrow_mut(|stack| {
+        let spawned = scope_name::<G>();
+
+        if activate(stack, scope_name::<S>()) {
+            let spawner = stack.last_mut().unwrap();
+            if !spawner.callees.contains(&spawned) {
+                spawner.callees.push(spawned);
+            }
+        }
+    });
+}
+
+pub(crate) fn enter_impl<S: Scope, D: Scope>() {
+    STACK.with_borrow_mut(|stack| {
+        let scope = scope_name::<S>();
+
+        if stack.iter().any(|frame| frame.scope == scope) {
+            activate(stack, scope);
+        } else if activate(stack, scope_name::<D>()) {
+            stack.push(Frame::new(scope));
+        }
+    });
+}
+
+/// Sets how violations detected on the current thread are reported.
+pub fn set_violation_mode(mode: ViolationMode) {
+    MODE.set(mode);
+}
+
+/// Returns the name of the scope currently being executed on this thread, if any.
+pub fn active_scope() -> Option<&'static str> {
+    STACK.with_borrow(|stack| stack.last().map(|frame| frame.scope))
+}
+
+/// Checks that the scope currently being executed has declared a dependency on `T` which permits a
+/// borrow of the given kind. This is called automatically by the wrappers in [`crate::cell`] but
+/// can also be used by other dynamically borrow-checked containers.
+#[track_caller]
+pub fn check_borrow<T: ?Sized + 'static>(c: &impl Scope, kind: BorrowKind) {
+    #[track_caller]
+    fn inner<S: Scope>(_c: &S, component: &'static str, kind: BorrowKind) {
+        let token_scope = scope_name::<S>();
+        let violation = STACK.with_borrow_mut(|stack| {
+            activate(stack, token_scope);
+            let active = stack.last().unwrap();
+
+            let declared = active
+                .deps
+                .iter()
+                .any(|&(dep, dep_kind)| dep == component && kind.is_covered_by(dep_kind));
+
+            (!declared).then_some(active.scope)
+        });
+
+        let Some(active_scope) = violation else {
+            return;
+        };
+
+        let mut msg = format!(
+            "saddle tracker: the scope {active_scope} borrowed the component {component} {} \
+             without declaring it.",
+            kind.adjective(),
+        );
+
+        if active_scope != token_scope {
+            msg.push_str(&format!(
+                " The borrow was performed through a token for the scope {token_scope}, which was \
+                 not entered from the active scope."
+            ));
+        }
+
+        match MODE.get() {
+            ViolationMode::Panic => panic!("{msg}"),
+            ViolationMode::Log => eprintln!("{msg}"),
+        }
+    }
+
+    inner(c, type_name::<T>(), kind)
+}
@@ -0,0 +1,51 @@
+use saddle::{
+    cell::{ScopedRefCell, TaggedRefCell},
+    decl, scope, Scope,
+};
+
+scope!(RootScope);
+scope!(CounterScope);
+
+struct Counters;
+
+fn count(c: &mut CounterScope, first: &TaggedRefCell<Counters, u32>, second: &ScopedRefCell<u64>) {
+    decl!(&*c => mut Counters);
+    decl!(&*c => ref u64);
+
+    *first.borrow_mut(c) += *second.borrow(c) as u32;
+}
+
+#[test]
+fn borrows_access_the_value() {
+    let first = TaggedRefCell::<Counters, u32>::new(1);
+    let second = ScopedRefCell::new(2u64);
+
+    count(decl!(RootScope::new() => call), &first, &second);
+
+    assert_eq!(first.into_inner(), 3);
+    assert_eq!(second.into_inner(), 2);
+}
+
+#[test]
+fn conflicting_borrows_fail() {
+    let c = RootScope::new();
+    decl!(c => mut u32);
+
+    let cell = ScopedRefCell::new(0u32);
+    let guard = cell.borrow(c);
+
+    assert!(cell.try_borrow(c).is_ok());
+    assert!(cell.try_borrow_mut(c).is_err());
+
+    drop(guard);
+    assert!(cell.try_borrow_mut(c).is_ok());
+}
+
+#[test]
+fn tagged_cells_are_debug_printed_with_their_tag() {
+    let cell = TaggedRefCell::<Counters, _>::from(5u32);
+    let printed = format!("{cell:?}");
+
+    assert!(printed.contains("Counters"), "{printed}");
+    assert!(printed.contains('5'), "{printed}");
+}
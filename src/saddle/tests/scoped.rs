@@ -0,0 +1,69 @@
+use saddle::{cell::ScopedRefCell, decl, scope, scoped, Scope};
+
+scope!(RootScope);
+scope!(RenderScope);
+scope!(AudioScope);
+
+fn render_frame(c: &mut RenderScope, frames: &ScopedRefCell<u64>) {
+    decl!(&*c => mut u64);
+    *frames.borrow_mut(c) += 1;
+}
+
+fn play(c: &mut AudioScope, volume: &ScopedRefCell<f32>) {
+    decl!(&*c => ref f32);
+    let _ = *volume.borrow(c);
+}
+
+#[scoped(mut u32, calls(RenderScope, sound: AudioScope))]
+fn update(counter: &ScopedRefCell<u32>, frames: &ScopedRefCell<u64>, volume: &ScopedRefCell<f32>) {
+    // The call tokens do not borrow `c`, so it can still be used before the calls are made.
+    *counter.borrow_mut(c) += 1;
+    render_frame(render, frames);
+    play(sound, volume);
+    *counter.borrow_mut(c) += 1;
+}
+
+#[test]
+fn call_tokens_enter_their_scopes() {
+    let counter = ScopedRefCell::new(0);
+    let frames = ScopedRefCell::new(0);
+    let volume = ScopedRefCell::new(1.0);
+
+    update(decl!(RootScope::new() => call), &counter, &frames, &volume);
+
+    assert_eq!(counter.into_inner(), 2);
+    assert_eq!(frames.into_inner(), 1);
+}
+
+mod reexported {
+    pub use saddle as engine_saddle;
+}
+
+#[scoped(crate = reexported::engine_saddle, name = RenamedScope, token = cx, ref u32)]
+fn renamed(counter: &ScopedRefCell<u32>) -> u32 {
+    *counter.borrow(cx)
+}
+
+struct Counter(ScopedRefCell<u32>);
+
+impl Counter {
+    #[scoped(scope = RenamedScope, ref u32)]
+    fn get(&self) -> u32 {
+        *self.0.borrow(c)
+    }
+}
+
+#[test]
+fn overrides_are_applied() {
+    let counter = ScopedRefCell::new(3);
+    assert_eq!(renamed(decl!(RootScope::new() => call), &counter), 3);
+
+    let counter = Counter(counter);
+    assert_eq!(counter.get(decl!(RootScope::new() => call)), 3);
+}
+
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,138 @@
+#![cfg(feature = "tracker")]
+
+use std::future::Future;
+
+use saddle::{cell::ScopedRefCell, decl, scope, Scope};
+
+scope!(RootScope);
+scope!(DeclaredScope);
+scope!(UndeclaredScope);
+
+fn declared(c: &mut DeclaredScope, cell: &ScopedRefCell<u32>) {
+    decl!(&*c => mut u32);
+    *cell.borrow_mut(c) += 1;
+}
+
+fn undeclared(c: &mut UndeclaredScope, cell: &ScopedRefCell<u32>) {
+    *cell.borrow_mut(c) += 1;
+}
+
+fn read_only(c: &mut DeclaredScope, cell: &ScopedRefCell<u32>) {
+    decl!(&*c => ref u32);
+    *cell.borrow_mut(c) += 1;
+}
+
+#[test]
+fn declared_borrow_is_accepted() {
+    let cell = ScopedRefCell::new(0);
+    declared(decl!(RootScope::new() => call), &cell);
+    assert_eq!(cell.into_inner(), 1);
+}
+
+#[test]
+#[should_panic(expected = "borrowed the component u32 mutably without declaring it")]
+fn undeclared_borrow_is_caught() {
+    let cell = ScopedRefCell::new(0);
+    undeclared(decl!(RootScope::new() => call), &cell);
+}
+
+#[test]
+#[should_panic(expected = "borrowed the component u32 mutably without declaring it")]
+fn borrow_exceeding_declaration_is_caught() {
+    let cell = ScopedRefCell::new(0);
+    read_only(decl!(RootScope::new() => call), &cell);
+}
+
+scope!(ScheduleScope);
+scope!(WorkerScope);
+
+fn work(c: &mut WorkerScope, cell: &ScopedRefCell<u32>) {
+    decl!(&*c => mut u32);
+    *cell.borrow_mut(c) += 1;
+}
+
+fn schedule(c: &mut ScheduleScope) {
+    let workers = [
+        decl!(&*c => spawn WorkerScope),
+        decl!(&*c => spawn WorkerScope),
+    ];
+    decl!(&*c => ref u8);
+
+    std::thread::scope(|s| {
+        for worker in workers {
+            s.spawn(move || {
+                let cell = ScopedRefCell::new(0);
+                work(worker, &cell);
+                assert_eq!(cell.into_inner(), 1);
+            });
+        }
+    });
+}
+
+#[test]
+fn spawned_scopes_are_entered_on_their_threads() {
+    schedule(decl!(RootScope::new() => call));
+}
+
+fn schedule_inline(c: &mut ScheduleScope, cell: &ScopedRefCell<u32>) {
+    let worker = decl!(&*c => spawn WorkerScope);
+    decl!(&*c => ref u8);
+
+    // An executor may run the spawned scope on the spawner's own thread.
+    work(worker, cell);
+}
+
+#[test]
+fn spawned_scopes_are_entered_on_the_spawning_thread() {
+    let cell = ScopedRefCell::new(0);
+    schedule_inline(decl!(RootScope::new() => call), &cell);
+    assert_eq!(cell.into_inner(), 1);
+}
+
+fn work_undeclared(c: &mut WorkerScope) {
+    let cell = ScopedRefCell::new(0u32);
+    *cell.borrow_mut(c) += 1;
+}
+
+fn schedule_undeclared(c: &mut ScheduleScope) {
+    decl!(&*c => mut u32);
+    let worker = decl!(&*c => spawn WorkerScope);
+
+    // The spawned scope does not inherit the declarations of its spawner.
+    let result = std::thread::spawn(move || work_undeclared(worker)).join();
+    assert!(result.is_err());
+}
+
+#[test]
+fn spawned_scopes_do_not_inherit_declarations() {
+    schedule_undeclared(decl!(RootScope::new() => call));
+}
+
+scope!(TaskScope);
+scope!(HandleScope);
+
+// Holding the borrow across the `.await` is what the hold declares.
+#[allow(clippy::await_holding_refcell_ref)]
+async fn handle(c: &mut HandleScope, cell: &ScopedRefCell<u32>) {
+    decl!(&*c => hold mut u32);
+
+    let mut value = cell.borrow_mut(c);
+    std::future::ready(()).await;
+    *value += 1;
+}
+
+async fn task(cell: &ScopedRefCell<u32>) {
+    let c = TaskScope::new();
+    decl!(&*c => task);
+    handle(decl!(c => call), cell).await;
+}
+
+#[test]
+fn held_borrows_are_declarations() {
+    let cell = ScopedRefCell::new(0);
+
+    let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+    assert!(std::pin::pin!(task(&cell)).poll(&mut cx).is_ready());
+
+    assert_eq!(cell.into_inner(), 1);
+}
@@ -0,0 +1,6 @@
+saddle::scope!(ExistingScope);
+
+#[saddle::scoped(name = NewScope, scope = ExistingScope)]
+fn update() {}
+
+fn main() {}
@@ -0,0 +1,8 @@
+trait Target {
+    type Scope;
+}
+
+#[saddle::scoped(calls(<u32 as Target>::Scope))]
+fn update() {}
+
+fn main() {}